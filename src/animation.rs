@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::sync::Arc;
+
+// region:    Components
+pub struct Animation{
+    clip: AnimationClip,
+    //Multiplier applied to the clip frame duration
+    pub speed: f32,
+    frame: u32,
+    forward: bool,
+    finished: bool,
+    //Last frame whose markers were fired
+    fired: Option<u32>,
+    timer: Timer
+}
+
+impl Animation {
+    pub fn play(&mut self, clip: AnimationClip) {
+        *self = Animation {
+            speed: self.speed,
+            ..Animation::from(clip)
+        };
+    }
+
+    pub fn restart(&mut self) {
+        self.play(self.clip.clone());
+    }
+
+    pub fn clip(&self) -> &AnimationClip {
+        &self.clip
+    }
+
+    //Only one-shot clips ever finish
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    fn advance(&mut self) {
+        let last = self.clip.size.saturating_sub(1);
+        match self.clip.mode {
+            PlaybackMode::Loop => {
+                self.frame = if self.frame < last { self.frame + 1 } else { 0 };
+            }
+            PlaybackMode::Once => {
+                if self.frame < last {
+                    self.frame += 1;
+                } else {
+                    self.finished = true;
+                }
+            }
+            PlaybackMode::PingPong => {
+                if last == 0 {return;}
+                if self.forward && self.frame == last {
+                    self.forward = false;
+                } else if !self.forward && self.frame == 0 {
+                    self.forward = true;
+                }
+                self.frame = if self.forward { self.frame + 1 } else { self.frame - 1 };
+            }
+        }
+    }
+
+    //Markers on the current frame that have not been fired yet
+    fn pending_markers(&mut self) -> Vec<FrameEventKind> {
+        let frame = self.frame;
+        if self.fired == Some(frame) {
+            return Vec::new();
+        }
+        self.fired = Some(frame);
+
+        self.clip.markers.iter()
+            .filter(|marker| marker.frame == frame)
+            .map(|marker| marker.kind)
+            .collect()
+    }
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        Animation::from(AnimationClip::looping(0, 1, 1.))
+    }
+}
+
+impl From<AnimationClip> for Animation {
+    fn from(clip: AnimationClip) -> Self {
+        Animation {
+            timer: Timer::from_seconds(clip.frame_duration, true),
+            clip,
+            speed: 1.,
+            frame: 0,
+            forward: true,
+            finished: false,
+            fired: None
+        }
+    }
+}
+// endregion:    Components
+
+// region:    Events
+pub struct AnimationFinished {
+    pub entity: Entity,
+    pub clip: AnimationClip
+}
+
+//Fired when a clip reaches a frame carrying a marker
+pub struct AnimationEvent {
+    pub entity: Entity,
+    pub kind: FrameEventKind
+}
+// endregion:    Events
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum FrameEventKind {
+    Jump,
+    Footstep,
+    Land,
+    WingFlap,
+    Thud
+}
+
+//Frame of a clip, relative to its first frame, that fires an event
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct FrameMarker {
+    pub frame: u32,
+    pub kind: FrameEventKind
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum PlaybackMode {
+    Loop,
+    Once,
+    PingPong
+}
+
+//Named range of frames inside a texture atlas
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationClip {
+    pub index: u32,
+    pub size: u32,
+    pub mode: PlaybackMode,
+    pub frame_duration: f32,
+    pub markers: Arc<[FrameMarker]>
+}
+
+impl AnimationClip {
+    pub fn looping(index: u32, size: u32, frame_duration: f32) -> Self {
+        AnimationClip::new(index, size, PlaybackMode::Loop, frame_duration)
+    }
+
+    pub fn once(index: u32, size: u32, frame_duration: f32) -> Self {
+        AnimationClip::new(index, size, PlaybackMode::Once, frame_duration)
+    }
+
+    pub fn ping_pong(index: u32, size: u32, frame_duration: f32) -> Self {
+        AnimationClip::new(index, size, PlaybackMode::PingPong, frame_duration)
+    }
+
+    pub fn new(index: u32, size: u32, mode: PlaybackMode, frame_duration: f32) -> Self {
+        AnimationClip { index, size, mode, frame_duration, markers: Arc::from(Vec::new()) }
+    }
+
+    pub fn with_markers(self, markers: Vec<FrameMarker>) -> Self {
+        AnimationClip { markers: Arc::from(markers), ..self }
+    }
+}
+
+// region:    Plugin
+pub struct AnimationPlugin;
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .add_event::<AnimationFinished>()
+            .add_event::<AnimationEvent>()
+            .add_system(animate_sprites.system());
+    }
+}
+// endregion:    Plugin
+
+fn animate_sprites(
+    time: Res<Time>,
+    mut finished_events: EventWriter<AnimationFinished>,
+    mut frame_events: EventWriter<AnimationEvent>,
+    mut query: Query<(Entity, &mut Animation, &mut TextureAtlasSprite)>,
+) {
+    for (entity, mut anim, mut sprite) in query.iter_mut() {
+        if anim.finished {continue;}
+
+        //Markers on the first frame fire as soon as the clip starts
+        for kind in anim.pending_markers() {
+            frame_events.send(AnimationEvent { entity, kind });
+        }
+
+        let delta = time.delta().mul_f32(anim.speed.max(0.));
+        anim.timer.tick(delta);
+
+        for _ in 0..anim.timer.times_finished() {
+            anim.advance();
+            if anim.finished {
+                finished_events.send(AnimationFinished {
+                    entity,
+                    clip: anim.clip.clone()
+                });
+                break;
+            }
+            for kind in anim.pending_markers() {
+                frame_events.send(AnimationEvent { entity, kind });
+            }
+        }
+
+        let index = anim.clip.index + anim.frame;
+        if sprite.index != index {
+            sprite.index = index;
+        }
+    }
+}
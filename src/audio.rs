@@ -0,0 +1,516 @@
+use bevy::prelude::*;
+use bevy_kira_audio::{Audio, AudioChannel, AudioSource};
+use serde::{Deserialize, Serialize};
+use settings::*;
+use enemies::*;
+use player::*;
+use pool::*;
+use controls::*;
+use gamestate::{GameState, GameMode};
+
+use std::collections::HashMap;
+use std::cmp::Ordering;
+
+use crate::settings;
+use crate::enemies;
+use crate::player;
+use crate::pool;
+use crate::controls;
+use crate::gamestate;
+
+// region:    Constants
+const SFX: [(SfxKind, &str); 11] = [
+    (SfxKind::Jump, "sounds/jump.mp3"),
+    (SfxKind::Footstep, "sounds/footstep.wav"),
+    (SfxKind::WingFlap, "sounds/wing_flap.wav"),
+    (SfxKind::Thud, "sounds/thud.wav"),
+    (SfxKind::Land, "sounds/land.wav"),
+    (SfxKind::Death, "sounds/death.wav"),
+    (SfxKind::Stomp, "sounds/stomp.wav"),
+    (SfxKind::Pickup, "sounds/pickup.wav"),
+    (SfxKind::MenuMove, "sounds/menu_move.wav"),
+    (SfxKind::MenuSelect, "sounds/menu_select.wav"),
+    (SfxKind::Hurt, "sounds/thud.wav")
+];
+const MUSIC: [(MusicKind, &str); 3] = [
+    (MusicKind::Menu, "music/menu.wav"),
+    (MusicKind::Playing, "music/playing.wav"),
+    (MusicKind::GameOver, "music/game_over.wav")
+];
+//Layers looped in sync with the playing track, silent until the run asks for them
+const STEMS: [(Stem, &str); 3] = [
+    (Stem::Drums, "music/playing_drums.wav"),
+    (Stem::Lead, "music/playing_lead.wav"),
+    (Stem::Tension, "music/playing_tension.wav")
+];
+const STINGER: &str = "music/stinger.wav";
+const STINGER_LENGTH: f32 = 1.5;
+//Bees on screen at once that count as a dense wave
+const DENSE_WAVE: usize = 3;
+//Stem gain change per second
+const STEM_FADE: f32 = 0.5;
+const BUZZ: &str = "sounds/buzz.wav";
+//Bees heard at once, the closest ones get a voice
+const BUZZ_VOICES: usize = 4;
+//Distance from the player where a buzz fades out completely
+const BUZZ_RANGE: f32 = 900.;
+const BUZZ_VOLUME: f32 = 0.5;
+// endregion:    Constants
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SfxKind {
+    Jump,
+    Footstep,
+    WingFlap,
+    Thud,
+    Land,
+    Death,
+    Stomp,
+    Pickup,
+    Hurt,
+    MenuMove,
+    MenuSelect
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MusicKind {
+    Menu,
+    Playing,
+    //Track of the loaded beatmap, registered once the beatmap is read
+    Rhythm,
+    GameOver
+}
+
+impl MusicKind {
+    //Calibration needs silence to hear the clicks, loading has nothing to play yet
+    fn for_state(state: &GameState, mode: GameMode) -> Option<Self> {
+        match (state, mode) {
+            (GameState::MainMenu, _) | (GameState::Shop, _) | (GameState::Settings, _) => Some(MusicKind::Menu),
+            (GameState::Loading, _) | (GameState::Calibration, _) => None,
+            (GameState::Playing, GameMode::Endless) | (GameState::Paused, GameMode::Endless) => Some(MusicKind::Playing),
+            (GameState::Playing, GameMode::Rhythm) | (GameState::Paused, GameMode::Rhythm) => Some(MusicKind::Rhythm),
+            (GameState::Dead, _) => Some(MusicKind::GameOver)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stem {
+    Drums,
+    Lead,
+    Tension
+}
+
+impl Stem {
+    //Gain the stem heads to for the current run intensity
+    fn target(&self, intensity: f32, dense_wave: bool) -> f32 {
+        match self {
+            Stem::Drums => ((intensity - 0.2) / 0.4).max(0.).min(1.),
+            Stem::Lead => ((intensity - 0.6) / 0.4).max(0.).min(1.),
+            Stem::Tension => if dense_wave { 1. } else { 0. }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeChannel {
+    Master,
+    Music,
+    Sfx
+}
+
+impl VolumeChannel {
+    pub const ALL: [VolumeChannel; 3] = [
+        VolumeChannel::Master,
+        VolumeChannel::Music,
+        VolumeChannel::Sfx
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            VolumeChannel::Master => "Master volume",
+            VolumeChannel::Music => "Music volume",
+            VolumeChannel::Sfx => "Effects volume"
+        }
+    }
+}
+
+// region:    Events
+//Gameplay asks for sounds through this instead of touching the audio backend
+pub struct PlaySfx(pub SfxKind);
+// endregion:    Events
+
+// region:    Resources
+pub struct Sounds {
+    pub sfx: HashMap<SfxKind, Handle<AudioSource>>,
+    pub music: HashMap<MusicKind, Handle<AudioSource>>,
+    pub stems: HashMap<Stem, Handle<AudioSource>>,
+    pub stinger: Handle<AudioSource>,
+    pub buzz: Handle<AudioSource>
+}
+
+//Stored with the rest of the settings
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+    pub muted: bool
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings {
+            master: 1.,
+            music: 0.6,
+            sfx: 0.8,
+            muted: false
+        }
+    }
+}
+
+impl AudioSettings {
+    pub fn volume(&self, channel: VolumeChannel) -> f32 {
+        match channel {
+            VolumeChannel::Master => self.master,
+            VolumeChannel::Music => self.music,
+            VolumeChannel::Sfx => self.sfx
+        }
+    }
+
+    pub fn adjust(&mut self, channel: VolumeChannel, delta: f32) {
+        let volume = match channel {
+            VolumeChannel::Master => &mut self.master,
+            VolumeChannel::Music => &mut self.music,
+            VolumeChannel::Sfx => &mut self.sfx
+        };
+        *volume = (*volume + delta).max(0.).min(1.);
+    }
+
+    //What a channel actually plays at once master and mute are applied
+    fn output(&self, channel: VolumeChannel) -> f32 {
+        if self.muted {
+            0.
+        } else {
+            self.master * self.volume(channel)
+        }
+    }
+}
+
+struct AudioChannels {
+    music: AudioChannel,
+    sfx: AudioChannel,
+    stems: HashMap<Stem, AudioChannel>,
+    buzz: Vec<AudioChannel>
+}
+
+//Bee looping on each buzz channel
+struct BuzzVoices(Vec<Option<Entity>>);
+
+struct CurrentMusic {
+    //Track looping on the music channel
+    track: Option<MusicKind>,
+    //Running while the game over stinger plays, the loop starts after it
+    stinger: Option<Timer>,
+    stem_gains: HashMap<Stem, f32>
+}
+// endregion:    Resources
+
+// region:    Plugin
+pub struct GameAudioPlugin;
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .add_event::<PlaySfx>()
+            .add_startup_system(load_sounds.system())
+            .add_system(play_sfx.system())
+            .add_system(music_for_state.system())
+            .add_system(stinger_end.system())
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(adaptive_stems.system())
+                    .with_system(enemy_buzz.system())
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing)
+                    .with_system(stop_buzz.system())
+            )
+            .add_system_set(
+                SystemSet::on_pause(GameState::Playing)
+                    .with_system(stop_buzz.system())
+                    .with_system(pause_rhythm_track.system())
+            )
+            .add_system_set(
+                SystemSet::on_resume(GameState::Playing)
+                    .with_system(resume_rhythm_track.system())
+            )
+            .add_system(mute_toggle.system())
+            .add_system(apply_volumes.system())
+            .insert_resource(AudioChannels {
+                music: AudioChannel::new("music".to_owned()),
+                sfx: AudioChannel::new("sfx".to_owned()),
+                stems: STEMS.iter()
+                    .map(|(stem, path)| (*stem, AudioChannel::new(path.to_string())))
+                    .collect(),
+                buzz: (0..BUZZ_VOICES)
+                    .map(|i| AudioChannel::new(format!("buzz_{}", i)))
+                    .collect()
+            })
+            .insert_resource(BuzzVoices(vec![None; BUZZ_VOICES]))
+            .insert_resource(CurrentMusic {
+                track: None,
+                stinger: None,
+                stem_gains: HashMap::new()
+            });
+    }
+}
+// endregion:    Plugin
+
+fn load_sounds(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>
+){
+    commands.insert_resource(Sounds {
+        sfx: SFX.iter().map(|(kind, path)| (*kind, asset_server.load(*path))).collect(),
+        music: MUSIC.iter().map(|(kind, path)| (*kind, asset_server.load(*path))).collect(),
+        stems: STEMS.iter().map(|(stem, path)| (*stem, asset_server.load(*path))).collect(),
+        stinger: asset_server.load(STINGER),
+        buzz: asset_server.load(BUZZ)
+    });
+}
+
+fn play_sfx(
+    audio: Res<Audio>,
+    channels: Res<AudioChannels>,
+    sounds: Res<Sounds>,
+    mut events: EventReader<PlaySfx>
+){
+    for PlaySfx(kind) in events.iter() {
+        if let Some(handle) = sounds.sfx.get(kind) {
+            audio.play_in_channel(handle.clone(), &channels.sfx);
+        }
+    }
+}
+
+//Swaps the looping track when the state calls for another one
+fn music_for_state(
+    audio: Res<Audio>,
+    channels: Res<AudioChannels>,
+    sounds: Res<Sounds>,
+    settings: Res<Settings>,
+    game_state: Res<State<GameState>>,
+    mode: Res<GameMode>,
+    mut current: ResMut<CurrentMusic>
+){
+    let wanted = MusicKind::for_state(game_state.current(), *mode);
+    if current.track == wanted {return;}
+
+    //Cut everything, stems included
+    audio.stop_channel(&channels.music);
+    for channel in channels.stems.values() {
+        audio.stop_channel(channel);
+    }
+    current.stinger = None;
+    current.stem_gains.clear();
+    let previous = current.track;
+    current.track = wanted;
+
+    let wanted = match wanted {
+        Some(track) => track,
+        None => return
+    };
+    match (previous, wanted) {
+        (Some(MusicKind::Playing), MusicKind::GameOver) | (Some(MusicKind::Rhythm), MusicKind::GameOver) => {
+            audio.play_in_channel(sounds.stinger.clone(), &channels.music);
+            current.stinger = Some(Timer::from_seconds(STINGER_LENGTH, false));
+        }
+        (_, MusicKind::Playing) => {
+            play_track(&audio, &channels, &sounds, wanted);
+            //Stems start with the track so they stay in sync, muted until needed
+            for (stem, handle) in sounds.stems.iter() {
+                let channel = &channels.stems[stem];
+                audio.set_volume_in_channel(0., channel);
+                audio.play_looped_in_channel(handle.clone(), channel);
+                current.stem_gains.insert(*stem, 0.);
+            }
+            audio.set_volume_in_channel(settings.audio.output(VolumeChannel::Music), &channels.music);
+        }
+        _ => play_track(&audio, &channels, &sounds, wanted)
+    }
+}
+
+fn play_track(
+    audio: &Audio,
+    channels: &AudioChannels,
+    sounds: &Sounds,
+    track: MusicKind
+){
+    if let Some(handle) = sounds.music.get(&track) {
+        audio.play_looped_in_channel(handle.clone(), &channels.music);
+    }
+}
+
+fn stinger_end(
+    time: Res<Time>,
+    audio: Res<Audio>,
+    channels: Res<AudioChannels>,
+    sounds: Res<Sounds>,
+    mut current: ResMut<CurrentMusic>
+){
+    let finished = match current.stinger.as_mut() {
+        Some(timer) => timer.tick(time.delta()).finished(),
+        None => false
+    };
+    if !finished {return;}
+
+    current.stinger = None;
+    if let Some(track) = current.track {
+        play_track(&audio, &channels, &sounds, track);
+    }
+}
+
+//Fades layers in and out following the difficulty and the number of bees around
+fn adaptive_stems(
+    time: Res<Time>,
+    audio: Res<Audio>,
+    channels: Res<AudioChannels>,
+    settings: Res<Settings>,
+    spawn_timer: Res<SpawnTimer>,
+    mut current: ResMut<CurrentMusic>,
+    enemy_query: Query<(&Pooled, With<Enemy>)>
+){
+    let intensity = spawn_timer.intensity();
+    let dense_wave = enemy_query.iter().filter(|(pooled, _)| pooled.active).count() >= DENSE_WAVE;
+    let music_volume = settings.audio.output(VolumeChannel::Music);
+    let step = STEM_FADE * time.delta_seconds();
+
+    for (stem, gain) in current.stem_gains.iter_mut() {
+        let target = stem.target(intensity, dense_wave);
+        let next = if *gain < target { (*gain + step).min(target) } else { (*gain - step).max(target) };
+
+        if next != *gain || settings.is_changed() {
+            *gain = next;
+            audio.set_volume_in_channel(music_volume * next, &channels.stems[stem]);
+        }
+    }
+}
+
+//Each close bee buzzes from where it is, so the ones still off screen can be heard coming
+fn enemy_buzz(
+    audio: Res<Audio>,
+    channels: Res<AudioChannels>,
+    sounds: Res<Sounds>,
+    settings: Res<Settings>,
+    mut voices: ResMut<BuzzVoices>,
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<(Entity, &Transform, &Pooled), With<Enemy>>
+){
+    let player_pos = match player_query.single() {
+        Ok(transform) => transform.translation,
+        Err(_) => return
+    };
+
+    //Closest active bees within range
+    let mut heard: Vec<(Entity, Vec3, f32)> = enemy_query.iter()
+        .filter(|(_, _, pooled)| pooled.active)
+        .map(|(entity, transform, _)| {
+            let offset = transform.translation - player_pos;
+            (entity, offset, offset.truncate().length())
+        })
+        .filter(|(_, _, distance)| *distance < BUZZ_RANGE)
+        .collect();
+    heard.sort_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(Ordering::Equal));
+    heard.truncate(BUZZ_VOICES);
+
+    //Free the voices of bees that went quiet
+    for (voice, owner) in voices.0.iter_mut().enumerate() {
+        if let Some(entity) = *owner {
+            if !heard.iter().any(|(heard_entity, _, _)| *heard_entity == entity) {
+                audio.stop_channel(&channels.buzz[voice]);
+                *owner = None;
+            }
+        }
+    }
+
+    let sfx_volume = settings.audio.output(VolumeChannel::Sfx);
+    for (entity, offset, distance) in heard {
+        let voice = match voices.0.iter().position(|owner| *owner == Some(entity)) {
+            Some(voice) => voice,
+            None => {
+                //Always a free one, at most BUZZ_VOICES bees are heard
+                let voice = voices.0.iter().position(|owner| owner.is_none()).unwrap();
+                audio.set_volume_in_channel(0., &channels.buzz[voice]);
+                audio.play_looped_in_channel(sounds.buzz.clone(), &channels.buzz[voice]);
+                voices.0[voice] = Some(entity);
+                voice
+            }
+        };
+
+        //Louder up close, panned 0 for left and 1 for right
+        let falloff = 1. - distance / BUZZ_RANGE;
+        let pan = (0.5 + offset.x / (2. * BUZZ_RANGE)).max(0.).min(1.);
+        audio.set_volume_in_channel(sfx_volume * BUZZ_VOLUME * falloff * falloff, &channels.buzz[voice]);
+        audio.set_panning_in_channel(pan, &channels.buzz[voice]);
+    }
+}
+
+fn stop_buzz(
+    audio: Res<Audio>,
+    channels: Res<AudioChannels>,
+    mut voices: ResMut<BuzzVoices>
+){
+    for (voice, owner) in voices.0.iter_mut().enumerate() {
+        if owner.take().is_some() {
+            audio.stop_channel(&channels.buzz[voice]);
+        }
+    }
+}
+
+//The beatmap clock stops while paused, its track has to stop with it
+fn pause_rhythm_track(
+    audio: Res<Audio>,
+    channels: Res<AudioChannels>,
+    mode: Res<GameMode>
+){
+    if *mode == GameMode::Rhythm {
+        audio.pause_channel(&channels.music);
+    }
+}
+
+fn resume_rhythm_track(
+    audio: Res<Audio>,
+    channels: Res<AudioChannels>,
+    mode: Res<GameMode>
+){
+    if *mode == GameMode::Rhythm {
+        audio.resume_channel(&channels.music);
+    }
+}
+
+fn mute_toggle(
+    keyboard_input: Res<Input<KeyCode>>,
+    game_state: Res<State<GameState>>,
+    rebinding: Res<Rebinding>,
+    mut settings: ResMut<Settings>
+){
+    //M is a letter of the name typed on the game over screen, or the key being bound
+    if *game_state.current() == GameState::Dead || rebinding.0.is_some() {return;}
+
+    if keyboard_input.just_pressed(KeyCode::M) {
+        settings.audio.muted = !settings.audio.muted;
+        settings.save();
+    }
+}
+
+//Also runs on the first frame, applying the saved settings at startup
+fn apply_volumes(
+    audio: Res<Audio>,
+    channels: Res<AudioChannels>,
+    settings: Res<Settings>
+){
+    if !settings.is_changed() {return;}
+
+    audio.set_volume_in_channel(settings.audio.output(VolumeChannel::Music), &channels.music);
+    audio.set_volume_in_channel(settings.audio.output(VolumeChannel::Sfx), &channels.sfx);
+}
@@ -0,0 +1,167 @@
+use bevy::prelude::*;
+use settings::*;
+use layout::*;
+use audio::*;
+use controls::*;
+use gamestate::{GameState};
+
+use std::collections::VecDeque;
+
+use crate::{Materials};
+use crate::settings;
+use crate::layout;
+use crate::audio;
+use crate::controls;
+use crate::gamestate;
+
+// region:    Constants
+const CLICK_INTERVAL: f32 = 0.6;
+//Only the latest taps are averaged
+const MAX_TAPS: usize = 8;
+// endregion:    Constants
+
+// region:    Resources
+//Metronome and the offsets of the taps against it
+#[derive(Default)]
+struct Calibration {
+    clock: f32,
+    clicks: u32,
+    offsets: VecDeque<f32>
+}
+
+impl Calibration {
+    fn average(&self) -> Option<f32> {
+        if self.offsets.is_empty() {return None;}
+        Some(self.offsets.iter().sum::<f32>() / self.offsets.len() as f32)
+    }
+}
+// endregion:    Resources
+
+// region:    Components
+struct CalibrationRoot;
+struct CalibrationText;
+// endregion:    Components
+
+// region:    Plugin
+pub struct CalibrationPlugin;
+impl Plugin for CalibrationPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .add_system_set(
+                SystemSet::on_enter(GameState::Calibration)
+                    .with_system(calibration_spawn.system())
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Calibration)
+                    .with_system(metronome.system())
+                    .with_system(calibration_input.system())
+                    .with_system(update_calibration_text.system())
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Calibration)
+                    .with_system(despawn_screen::<CalibrationRoot>.system())
+            )
+            .insert_resource(Calibration::default());
+    }
+}
+// endregion:    Plugin
+
+fn calibration_spawn(
+    mut commands: Commands,
+    materials: Res<Materials>,
+    ui: Res<UiMaterials>,
+    mut calibration: ResMut<Calibration>
+){
+    *calibration = Calibration::default();
+
+    let style = TextStyle {
+        font: materials.font.clone(),
+        font_size: 40.0,
+        color: TEXT_COLOR,
+    };
+
+    commands
+        .spawn_bundle(centered(&ui))
+        .insert(CalibrationRoot)
+        .with_children(|screen| {
+            screen.spawn_bundle(label(vec![TextSection {
+                value: "Calibration".to_string(),
+                style: TextStyle { font_size: 60.0, ..style.clone() }
+            }]));
+            screen.spawn_bundle(label(vec![TextSection {
+                value: "Tap jump on each click".to_string(),
+                style: style.clone()
+            }]));
+            //Measured offset, then the saved one
+            screen.spawn_bundle(label(vec![
+                TextSection { value: String::new(), style: TextStyle { color: HIGHLIGHT_COLOR, ..style.clone() } },
+                TextSection { value: String::new(), style: TextStyle { font_size: 34.0, ..style.clone() } }
+            ])).insert(CalibrationText);
+            screen.spawn_bundle(label(vec![TextSection {
+                value: "Confirm to save, back to leave".to_string(),
+                style: TextStyle { font_size: 34.0, ..style.clone() }
+            }]));
+            screen.spawn_bundle(label(vec![TextSection {
+                value: "< Back".to_string(),
+                style: TextStyle { font_size: 30.0, ..style.clone() }
+            }]))
+                .insert(ClickAction(Action::Back))
+                .insert(Interaction::default());
+        });
+}
+
+fn metronome(
+    time: Res<Time>,
+    mut calibration: ResMut<Calibration>,
+    mut sfx: EventWriter<PlaySfx>
+){
+    calibration.clock += time.delta_seconds();
+
+    let clicks = (calibration.clock / CLICK_INTERVAL) as u32;
+    if clicks > calibration.clicks {
+        calibration.clicks = clicks;
+        sfx.send(PlaySfx(SfxKind::MenuMove));
+    }
+}
+
+//A tap after the nearest click means the sound came late
+fn calibration_input(
+    actions: Res<Actions>,
+    mut calibration: ResMut<Calibration>,
+    mut settings: ResMut<Settings>,
+    mut sfx: EventWriter<PlaySfx>
+){
+    if actions.just_pressed(Action::Jump) && calibration.clicks > 0 {
+        let nearest = (calibration.clock / CLICK_INTERVAL).round() * CLICK_INTERVAL;
+        let offset = calibration.clock - nearest;
+
+        calibration.offsets.push_back(offset);
+        if calibration.offsets.len() > MAX_TAPS {
+            calibration.offsets.pop_front();
+        }
+    }
+
+    if actions.just_pressed(Action::Confirm) {
+        if let Some(average) = calibration.average() {
+            settings.rhythm_latency = average;
+            settings.save();
+            sfx.send(PlaySfx(SfxKind::MenuSelect));
+        }
+    }
+}
+
+fn update_calibration_text(
+    calibration: Res<Calibration>,
+    settings: Res<Settings>,
+    mut query: Query<(&mut Text, With<CalibrationText>)>
+){
+    if !calibration.is_changed() && !settings.is_changed() {return;}
+
+    if let Ok((mut text, _)) = query.single_mut() {
+        text.sections[0].value = match calibration.average() {
+            Some(average) => format!("Offset {:+.0} ms ({} taps)", average * 1000., calibration.offsets.len()),
+            None => "Offset -".to_string()
+        };
+        text.sections[1].value = format!("\nSaved {:+.0} ms", settings.rhythm_latency * 1000.);
+    }
+}
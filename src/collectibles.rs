@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+use bevy::sprite::collide_aabb::collide;
+use serde::{Deserialize, Serialize};
+use physics::*;
+use player::*;
+use enemies::*;
+use culling::*;
+use sheets::*;
+use score::*;
+use audio::*;
+use gamestate::{GameState};
+
+use rand::{thread_rng, Rng};
+
+use crate::{Materials, TIME_STEP};
+use crate::physics;
+use crate::player;
+use crate::enemies;
+use crate::culling;
+use crate::sheets;
+use crate::score;
+use crate::audio;
+use crate::gamestate;
+use crate::storage;
+
+// region:    Constants
+const CANDY_SIZE: f32 = 28.;
+const WALLET_FILE: &str = "wallet.ron";
+// endregion:    Constants
+
+// region:    Resources
+struct CandySpawnTimer(Timer);
+
+//Candies picked up during the current run
+pub struct CandyCount(pub u32);
+
+//Candies banked across runs
+#[derive(Default, Serialize, Deserialize)]
+pub struct Wallet {
+    pub candies: u32
+}
+
+impl Wallet {
+    pub fn save(&self) {
+        storage::save(WALLET_FILE, self);
+    }
+}
+// endregion:    Resources
+
+// region:    Components
+pub struct Candy;
+// endregion:    Components
+
+// region:    Plugin
+pub struct CollectiblesPlugin;
+impl Plugin for CollectiblesPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(reset_candy_count.system())
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(candy_spawn.system())
+                    .with_system(candy_movement.system())
+                    .with_system(candy_pickup.system())
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Dead)
+                    .with_system(candy_movement.system())
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::Dead)
+                    .with_system(bank_candies.system())
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Dead)
+                    .with_system(candy_cleanup.system())
+            )
+            .insert_resource(CandySpawnTimer(Timer::from_seconds(1.2, true)))
+            .insert_resource(CandyCount(0))
+            .insert_resource(storage::load::<Wallet>(WALLET_FILE));
+    }
+}
+// endregion:    Plugin
+
+fn candy_spawn(
+    mut commands: Commands,
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut spawn_timer: ResMut<CandySpawnTimer>,
+    director: Res<SpawnDirector>,
+    enemy_settings: Res<EnemySpawnSettings>,
+    materials: Res<Materials>,
+    view: Res<ViewRect>,
+    player_alive: Res<PlayerAlive>
+){
+    if !player_alive.0 {return;}
+
+    spawn_timer.0.tick(time.delta().mul_f32(time_scale.0));
+    if !spawn_timer.0.finished() {
+        return;
+    }
+
+    //Not every tick drops a candy, keeps them a bit rare
+    let mut rng = thread_rng();
+    if rng.gen_bool(0.5) {return;}
+
+    let margin = CANDY_SIZE / 2.;
+    let lane = director.safe_lane();
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            material: materials.candy.clone(),
+            sprite: Sprite::new(Vec2::new(CANDY_SIZE, CANDY_SIZE)),
+            transform: Transform {
+                translation: Vec3::new(view.max.x + margin, SpawnDirector::lane_y(lane), 12.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(Candy)
+        .insert(CullBounds{margin})
+        .insert(Velocity(Vec2::new(enemy_settings.speed, 0.)));
+}
+
+fn candy_movement(
+    time_scale: Res<TimeScale>,
+    mut query: Query<(&Velocity, &mut Transform, With<Candy>)>
+){
+    for (velocity, mut transform, _) in query.iter_mut() {
+        transform.translation.x += velocity.0.x * time_scale.0 * TIME_STEP;
+        transform.translation.y += velocity.0.y * time_scale.0 * TIME_STEP;
+    }
+}
+
+fn candy_pickup(
+    mut commands: Commands,
+    mut count: ResMut<CandyCount>,
+    mut score_events: EventWriter<ScoreEvent>,
+    mut sfx: EventWriter<PlaySfx>,
+    player_alive: Res<PlayerAlive>,
+    sheets: Res<Assets<SpriteSheetMeta>>,
+    player_query: Query<(&Transform, &Sheet, With<Player>)>,
+    candy_query: Query<(Entity, &Transform, With<Candy>)>
+){
+    if !player_alive.0 {return;}
+
+    if let Ok((player_tf, sheet, _)) = player_query.single() {
+        let (player_pos, player_size) = match sheets.get(&sheet.0) {
+            Some(meta) => meta.hitbox("pickup", player_tf),
+            None => return
+        };
+
+        for (entity, candy_tf, _) in candy_query.iter() {
+            let collision = collide(player_pos, player_size, candy_tf.translation, Vec2::new(CANDY_SIZE, CANDY_SIZE));
+
+            if collision.is_some() {
+                count.0 += 1;
+                score_events.send(ScoreEvent{bonus: Bonus::Candy, position: candy_tf.translation});
+                sfx.send(PlaySfx(SfxKind::Pickup));
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+fn reset_candy_count(
+    mut count: ResMut<CandyCount>
+){
+    count.0 = 0;
+}
+
+fn bank_candies(
+    count: Res<CandyCount>,
+    mut wallet: ResMut<Wallet>
+){
+    wallet.candies += count.0;
+    wallet.save();
+}
+
+fn candy_cleanup(
+    mut commands: Commands,
+    query: Query<(Entity, With<Candy>)>
+){
+    for (entity, _) in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
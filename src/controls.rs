@@ -0,0 +1,390 @@
+use bevy::prelude::*;
+use bevy::input::InputSystem;
+use serde::{Deserialize, Serialize};
+use settings::*;
+use toasts::*;
+use gamestate::{GameState};
+
+use std::collections::{HashMap, HashSet};
+
+use crate::settings;
+use crate::toasts;
+use crate::gamestate;
+
+// region:    Constants
+//How far the stick has to lean before it counts as a direction
+const STICK_THRESHOLD: f32 = 0.5;
+//Keys with a fixed job outside the actions: mute, the main menu shortcuts and erasing a letter of a name
+const RESERVED_KEYS: [KeyCode; 6] = [KeyCode::M, KeyCode::R, KeyCode::S, KeyCode::O, KeyCode::C, KeyCode::Back];
+// endregion:    Constants
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Pause,
+    Confirm,
+    Back,
+    MenuUp,
+    MenuDown,
+    MenuLeft,
+    MenuRight
+}
+
+impl Action {
+    pub const ALL: [Action; 10] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Jump,
+        Action::Pause,
+        Action::Confirm,
+        Action::Back,
+        Action::MenuUp,
+        Action::MenuDown,
+        Action::MenuLeft,
+        Action::MenuRight
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Jump => "Jump",
+            Action::Pause => "Pause",
+            Action::Confirm => "Confirm",
+            Action::Back => "Back",
+            Action::MenuUp => "Menu up",
+            Action::MenuDown => "Menu down",
+            Action::MenuLeft => "Menu left",
+            Action::MenuRight => "Menu right"
+        }
+    }
+
+    fn default_keys(&self) -> Vec<KeyCode> {
+        match self {
+            Action::MoveLeft => vec![KeyCode::A],
+            Action::MoveRight => vec![KeyCode::D],
+            Action::Jump => vec![KeyCode::W, KeyCode::Space],
+            Action::Pause => vec![KeyCode::P],
+            Action::Confirm => vec![KeyCode::Return],
+            Action::Back => vec![KeyCode::Escape],
+            Action::MenuUp => vec![KeyCode::Up],
+            Action::MenuDown => vec![KeyCode::Down],
+            Action::MenuLeft => vec![KeyCode::Left],
+            Action::MenuRight => vec![KeyCode::Right]
+        }
+    }
+
+    //Same layout on every pad, only keys can be rebound. Confirm stays off the jump button
+    //so a confirm can not jump, or a jump confirm, on screens that read both
+    fn gamepad_buttons(&self) -> Vec<GamepadButtonType> {
+        match self {
+            Action::MoveLeft => vec![GamepadButtonType::DPadLeft],
+            Action::MoveRight => vec![GamepadButtonType::DPadRight],
+            Action::Jump => vec![GamepadButtonType::South],
+            Action::Pause => vec![GamepadButtonType::Start],
+            Action::Confirm => vec![GamepadButtonType::Start],
+            Action::Back => vec![GamepadButtonType::East, GamepadButtonType::Select],
+            Action::MenuUp => vec![GamepadButtonType::DPadUp],
+            Action::MenuDown => vec![GamepadButtonType::DPadDown],
+            Action::MenuLeft => vec![GamepadButtonType::DPadLeft],
+            Action::MenuRight => vec![GamepadButtonType::DPadRight]
+        }
+    }
+
+    //Left stick axis and the side of it that triggers the action
+    fn stick(&self) -> Option<(GamepadAxisType, f32)> {
+        match self {
+            Action::MoveLeft => Some((GamepadAxisType::LeftStickX, -1.)),
+            Action::MoveRight => Some((GamepadAxisType::LeftStickX, 1.)),
+            Action::MenuUp => Some((GamepadAxisType::LeftStickY, 1.)),
+            Action::MenuDown => Some((GamepadAxisType::LeftStickY, -1.)),
+            Action::MenuLeft => Some((GamepadAxisType::LeftStickX, -1.)),
+            Action::MenuRight => Some((GamepadAxisType::LeftStickX, 1.)),
+            _ => None
+        }
+    }
+}
+
+// region:    Resources
+//Keys bound to each action, stored with the settings
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct Bindings(HashMap<Action, Vec<KeyCode>>);
+
+impl Bindings {
+    //Actions missing from the saved file, or left without keys, keep their default keys
+    pub fn keys(&self, action: Action) -> Vec<KeyCode> {
+        self.0.get(&action)
+            .filter(|keys| !keys.is_empty())
+            .cloned()
+            .unwrap_or_else(|| action.default_keys())
+    }
+
+    //The key leaves any other action, one key only ever does one thing.
+    //An action losing its last key takes the old keys of the one being bound instead.
+    //Reserved keys are refused and nothing changes
+    pub fn bind(&mut self, action: Action, key: KeyCode) -> bool {
+        if RESERVED_KEYS.contains(&key) {return false;}
+
+        let previous = self.keys(action);
+        for other in Action::ALL.iter().filter(|other| **other != action) {
+            let keys = self.keys(*other);
+            if !keys.contains(&key) {continue;}
+
+            let mut remaining: Vec<KeyCode> = keys.into_iter().filter(|bound| *bound != key).collect();
+            if remaining.is_empty() {
+                remaining = previous.iter().copied().filter(|old| *old != key).collect();
+            }
+            self.0.insert(*other, remaining);
+        }
+        self.0.insert(action, vec![key]);
+        true
+    }
+}
+
+//Actions held and started this frame, what gameplay and menus read instead of keys
+#[derive(Default)]
+pub struct Actions {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    //Stick directions held last frame, sticks have no just pressed of their own
+    stick: HashSet<Action>
+}
+
+impl Actions {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+}
+
+//Action waiting for a new key on the settings screen
+pub struct Rebinding(pub Option<Action>);
+
+//Pads currently plugged in, in connection order
+#[derive(Default)]
+pub struct Gamepads(Vec<Gamepad>);
+// endregion:    Resources
+
+// region:    Components
+//UI node that triggers an action when clicked or touched
+pub struct ClickAction(pub Action);
+// endregion:    Components
+
+// region:    Plugin
+pub struct ControlsPlugin;
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .insert_resource(Actions::default())
+            .insert_resource(Rebinding(None))
+            .insert_resource(Gamepads::default())
+            .add_system_to_stage(CoreStage::PreUpdate, gamepad_connections.system().after(InputSystem))
+            .add_system_to_stage(CoreStage::PreUpdate, resolve_actions.system().after(InputSystem));
+    }
+}
+// endregion:    Plugin
+
+//Reads bevy's gamepad events, so pads plugged in mid game and synthetic GamepadEventRaw both show up
+fn gamepad_connections(
+    mut gamepads: ResMut<Gamepads>,
+    mut game_state: ResMut<State<GameState>>,
+    mut events: EventReader<GamepadEvent>,
+    mut toasts: EventWriter<Toast>
+){
+    for GamepadEvent(gamepad, event) in events.iter() {
+        match event {
+            GamepadEventType::Connected => {
+                if !gamepads.0.contains(gamepad) {
+                    gamepads.0.push(*gamepad);
+                }
+                toasts.send(Toast(format!("Gamepad {} connected", gamepad.0 + 1)));
+            }
+            GamepadEventType::Disconnected => {
+                gamepads.0.retain(|connected| connected != gamepad);
+                toasts.send(Toast(format!("Gamepad {} disconnected", gamepad.0 + 1)));
+
+                //Losing the pad mid run should not lose the run
+                if *game_state.current() == GameState::Playing {
+                    match game_state.push(GameState::Paused) {
+                        Ok(_) => {println!("Paused State");}
+                        Err(_) => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn resolve_actions(
+    keyboard_input: Res<Input<KeyCode>>,
+    button_input: Res<Input<GamepadButton>>,
+    mouse_input: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    axes: Res<Axis<GamepadAxis>>,
+    gamepads: Res<Gamepads>,
+    settings: Res<Settings>,
+    rebinding: Res<Rebinding>,
+    mut actions: ResMut<Actions>,
+    click_query: Query<(&Interaction, &ClickAction), Changed<Interaction>>,
+    hover_query: Query<&Interaction, With<ClickAction>>
+){
+    actions.pressed.clear();
+    actions.just_pressed.clear();
+
+    //Every pad drives the same player
+    let stick: HashSet<Action> = Action::ALL.iter()
+        .filter(|action| match action.stick() {
+            Some((axis, side)) => gamepads.0.iter().any(|gamepad| {
+                axes.get(GamepadAxis(*gamepad, axis)).unwrap_or(0.) * side > STICK_THRESHOLD
+            }),
+            None => false
+        })
+        .copied()
+        .collect();
+    let last_stick = std::mem::replace(&mut actions.stick, stick.clone());
+
+    //Keys go to the rebinding screen while it waits for one
+    if rebinding.0.is_some() {return;}
+
+    for action in Action::ALL.iter() {
+        let keys = settings.bindings.keys(*action);
+        let buttons: Vec<GamepadButton> = gamepads.0.iter()
+            .flat_map(|gamepad| action.gamepad_buttons().into_iter().map(move |button| GamepadButton(*gamepad, button)))
+            .collect();
+
+        let pressed = keys.iter().any(|key| keyboard_input.pressed(*key))
+            || buttons.iter().any(|button| button_input.pressed(*button))
+            || stick.contains(action);
+        let just_pressed = keys.iter().any(|key| keyboard_input.just_pressed(*key))
+            || buttons.iter().any(|button| button_input.just_pressed(*button))
+            || (stick.contains(action) && !last_stick.contains(action));
+
+        if pressed {
+            actions.pressed.insert(*action);
+        }
+        if just_pressed {
+            actions.just_pressed.insert(*action);
+        }
+    }
+
+    //In one-button mode a click or touch anywhere is the button, except on the clickable labels
+    let over_label = hover_query.iter().any(|interaction| *interaction != Interaction::None);
+    if settings.one_button && !over_label {
+        if mouse_input.pressed(MouseButton::Left) || touches.iter().next().is_some() {
+            actions.pressed.insert(Action::Jump);
+        }
+        if mouse_input.just_pressed(MouseButton::Left) || touches.iter_just_pressed().next().is_some() {
+            actions.just_pressed.insert(Action::Jump);
+        }
+    }
+
+    for (interaction, click) in click_query.iter() {
+        if *interaction == Interaction::Clicked {
+            actions.pressed.insert(click.0);
+            actions.just_pressed.insert(click.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::app::Events;
+    use bevy::input::InputPlugin;
+    use bevy::input::gamepad::GamepadEventRaw;
+
+    const PAD: Gamepad = Gamepad(0);
+
+    //Input and controls alone, fed the same raw events gilrs would send
+    fn app() -> AppBuilder {
+        let mut app = App::build();
+        app
+            .add_plugin(InputPlugin)
+            .add_state(GameState::Playing)
+            .insert_resource(Settings::default())
+            .add_event::<Toast>()
+            .add_plugin(ControlsPlugin);
+        app
+    }
+
+    fn send(app: &mut AppBuilder, event: GamepadEventType) {
+        app.app.world
+            .get_resource_mut::<Events<GamepadEventRaw>>()
+            .unwrap()
+            .send(GamepadEventRaw(PAD, event));
+    }
+
+    fn actions(app: &AppBuilder) -> &Actions {
+        app.app.world.get_resource::<Actions>().unwrap()
+    }
+
+    #[test]
+    fn pad_buttons_and_stick_drive_actions() {
+        let mut app = app();
+        send(&mut app, GamepadEventType::Connected);
+        app.app.update();
+
+        send(&mut app, GamepadEventType::ButtonChanged(GamepadButtonType::South, 1.));
+        send(&mut app, GamepadEventType::AxisChanged(GamepadAxisType::LeftStickX, -1.));
+        app.app.update();
+        assert!(actions(&app).just_pressed(Action::Jump));
+        assert!(actions(&app).pressed(Action::MoveLeft));
+        assert!(actions(&app).just_pressed(Action::MoveLeft));
+        assert!(!actions(&app).pressed(Action::Confirm));
+
+        //Held down, pressed but not started again
+        app.app.update();
+        assert!(actions(&app).pressed(Action::Jump));
+        assert!(!actions(&app).just_pressed(Action::Jump));
+        assert!(!actions(&app).just_pressed(Action::MoveLeft));
+
+        send(&mut app, GamepadEventType::ButtonChanged(GamepadButtonType::South, 0.));
+        send(&mut app, GamepadEventType::AxisChanged(GamepadAxisType::LeftStickX, 0.));
+        app.app.update();
+        assert!(!actions(&app).pressed(Action::Jump));
+        assert!(!actions(&app).pressed(Action::MoveLeft));
+    }
+
+    #[test]
+    fn unplugging_mid_run_pauses() {
+        let mut app = app();
+        send(&mut app, GamepadEventType::Connected);
+        app.app.update();
+        send(&mut app, GamepadEventType::ButtonChanged(GamepadButtonType::South, 1.));
+        app.app.update();
+        assert!(actions(&app).pressed(Action::Jump));
+
+        send(&mut app, GamepadEventType::Disconnected);
+        app.app.update();
+        let state = app.app.world.get_resource::<State<GameState>>().unwrap();
+        assert_eq!(*state.current(), GameState::Paused);
+
+        app.app.update();
+        assert!(!actions(&app).pressed(Action::Jump));
+    }
+
+    #[test]
+    fn binding_the_last_key_of_an_action_swaps_keys() {
+        let mut bindings = Bindings::default();
+        bindings.bind(Action::Jump, KeyCode::Return);
+
+        assert_eq!(bindings.keys(Action::Jump), vec![KeyCode::Return]);
+        assert_eq!(bindings.keys(Action::Confirm), vec![KeyCode::W, KeyCode::Space]);
+    }
+
+    #[test]
+    fn reserved_keys_can_not_be_bound() {
+        let mut bindings = Bindings::default();
+
+        assert!(!bindings.bind(Action::Jump, KeyCode::M));
+        assert!(!bindings.bind(Action::MenuDown, KeyCode::S));
+        assert_eq!(bindings.keys(Action::Jump), vec![KeyCode::W, KeyCode::Space]);
+        assert_eq!(bindings.keys(Action::MenuDown), vec![KeyCode::Down]);
+    }
+}
@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+use physics::*;
+use pool::Pooled;
+
+use crate::{MainCamera, WinSize};
+use crate::physics;
+use crate::pool;
+
+// region:    Resources
+//World space rectangle currently seen by the main camera
+#[derive(Default)]
+pub struct ViewRect {
+    pub min: Vec2,
+    pub max: Vec2
+}
+// endregion:    Resources
+
+// region:    Components
+//Extra distance past the view edge before the entity counts as offscreen
+pub struct CullBounds {
+    pub margin: f32
+}
+// endregion:    Components
+
+// region:    Events
+pub struct Offscreen(pub Entity);
+// endregion:    Events
+
+// region:    Plugin
+pub struct CullingPlugin;
+impl Plugin for CullingPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .insert_resource(ViewRect::default())
+            .add_event::<Offscreen>()
+            .add_system_to_stage(CoreStage::PreUpdate, update_view_rect.system())
+            .add_system_to_stage(CoreStage::PostUpdate, cull_offscreen.system());
+    }
+}
+// endregion:    Plugin
+
+fn update_view_rect(
+    windows: Res<Windows>,
+    mut win_size: Option<ResMut<WinSize>>,
+    mut view: ResMut<ViewRect>,
+    camera_query: Query<(&Transform, With<MainCamera>)>
+){
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return
+    };
+
+    if let Some(win_size) = win_size.as_mut() {
+        win_size.w = window.width();
+        win_size.h = window.height();
+    }
+
+    if let Ok((camera_tf, _)) = camera_query.single() {
+        let half = Vec2::new(window.width() * camera_tf.scale.x, window.height() * camera_tf.scale.y) / 2.;
+        let center = camera_tf.translation.truncate();
+        view.min = center - half;
+        view.max = center + half;
+    }
+}
+
+//Only entities heading away from the view are culled, so spawns past the edge survive
+fn cull_offscreen(
+    mut commands: Commands,
+    view: Res<ViewRect>,
+    mut events: EventWriter<Offscreen>,
+    query: Query<(Entity, &Transform, &Velocity, &CullBounds, &Visible, Option<&Pooled>)>
+){
+    for (entity, transform, velocity, bounds, visible, pooled) in query.iter() {
+        if !visible.is_visible {continue;}
+
+        let pos = transform.translation.truncate();
+        let leaving = (pos.x < view.min.x - bounds.margin && velocity.0.x <= 0.)
+            || (pos.x > view.max.x + bounds.margin && velocity.0.x >= 0.)
+            || (pos.y < view.min.y - bounds.margin && velocity.0.y <= 0.)
+            || (pos.y > view.max.y + bounds.margin && velocity.0.y >= 0.);
+
+        if !leaving {continue;}
+
+        events.send(Offscreen(entity));
+        if pooled.is_none() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+use bevy::diagnostic::DiagnosticId;
+use std::cmp::Ordering;
+use animation::*;
+use physics::*;
+use pool::*;
+use sheets::*;
+use audio::*;
+use player::*;
+use enemies::*;
+
+use crate::{TIME_STEP};
+use crate::animation;
+use crate::physics;
+use crate::pool;
+use crate::sheets;
+use crate::audio;
+use crate::player;
+use crate::enemies;
+
+// region:    Constants
+const PARTICLE_POOL_DIAGNOSTICS: [DiagnosticId; 3] = [
+    DiagnosticId::from_u128(0x2d4f8a61_93c7_4b52_8e0d_51a7c3f9e201),
+    DiagnosticId::from_u128(0x2d4f8a61_93c7_4b52_8e0d_51a7c3f9e202),
+    DiagnosticId::from_u128(0x2d4f8a61_93c7_4b52_8e0d_51a7c3f9e203),
+];
+const DUST_COLOR: Color = Color::rgb(0.82, 0.74, 0.62);
+const DUST_SIZE: f32 = 10.;
+const DUST_LIFETIME: f32 = 0.3;
+// endregion:    Constants
+
+// region:    Resources
+struct DustMaterial(Handle<ColorMaterial>);
+// endregion:    Resources
+
+// region:    Components
+pub struct Particle {
+    lifetime: Timer
+}
+// endregion:    Components
+
+// region:    Plugin
+pub struct EffectsPlugin;
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .add_plugin(PoolPlugin::<Particle>::new("particle_pool", PARTICLE_POOL_DIAGNOSTICS))
+            .add_startup_system(dust_material.system())
+            .add_system(frame_event_sounds.system())
+            .add_system(frame_event_dust.system())
+            .add_system(particles_update.system());
+    }
+}
+// endregion:    Plugin
+
+fn dust_material(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>
+){
+    commands.insert_resource(DustMaterial(materials.add(DUST_COLOR.into())));
+}
+
+fn frame_event_sounds(
+    mut sfx: EventWriter<PlaySfx>,
+    mut events: EventReader<AnimationEvent>,
+    visible_query: Query<&Visible>,
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<(Entity, &Transform, &Pooled), With<Enemy>>
+){
+    //Every bee already buzzes, only the closest one flaps out loud
+    let nearest_bee = player_query.single().ok().and_then(|player_tf| {
+        enemy_query.iter()
+            .filter(|(_, _, pooled)| pooled.active)
+            .map(|(entity, transform, _)| (entity, transform.translation.distance(player_tf.translation)))
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))
+            .map(|(entity, _)| entity)
+    });
+
+    for event in events.iter() {
+        //Parked entities keep animating but stay silent
+        if let Ok(visible) = visible_query.get(event.entity) {
+            if !visible.is_visible {continue;}
+        }
+
+        let kind = match event.kind {
+            FrameEventKind::Jump => SfxKind::Jump,
+            FrameEventKind::Footstep => SfxKind::Footstep,
+            FrameEventKind::Land => SfxKind::Land,
+            FrameEventKind::WingFlap if nearest_bee == Some(event.entity) => SfxKind::WingFlap,
+            FrameEventKind::WingFlap => continue,
+            FrameEventKind::Thud => SfxKind::Thud
+        };
+        sfx.send(PlaySfx(kind));
+    }
+}
+
+fn frame_event_dust(
+    mut commands: Commands,
+    mut pool: ResMut<Pool<Particle>>,
+    dust: Res<DustMaterial>,
+    sheets: Res<Assets<SpriteSheetMeta>>,
+    mut events: EventReader<AnimationEvent>,
+    source_query: Query<(&Transform, Option<&Sheet>), Without<Particle>>,
+    mut particle_query: Query<(&mut Particle, &mut Pooled, &mut Visible, &mut Transform, &mut Velocity)>
+){
+    for event in events.iter() {
+        match event.kind {
+            FrameEventKind::Footstep | FrameEventKind::Land | FrameEventKind::Thud => {}
+            _ => continue
+        }
+
+        let (source_tf, sheet) = match source_query.get(event.entity) {
+            Ok(source) => source,
+            Err(_) => continue
+        };
+        //Dust rises from the sheet pivot, the feet of the character
+        let pivot = sheet
+            .and_then(|sheet| sheets.get(&sheet.0))
+            .map(|meta| meta.pivot_position(source_tf))
+            .unwrap_or(source_tf.translation);
+        let feet = pivot + Vec3::new(0., 0., 1.);
+
+        //A puff of dust going both ways
+        for direction in [-1., 1.].iter() {
+            let velocity = Vec2::new(40. * direction, 25.);
+
+            if let Some(entity) = pool.acquire() {
+                if let Ok((mut particle, mut pooled, mut visible, mut transform, mut vel)) = particle_query.get_mut(entity) {
+                    particle.lifetime.reset();
+                    pooled.active = true;
+                    visible.is_visible = true;
+                    transform.translation = feet;
+                    transform.scale = Vec3::ONE;
+                    vel.0 = velocity;
+                    continue;
+                }
+            }
+
+            commands
+                .spawn_bundle(SpriteBundle {
+                    material: dust.0.clone(),
+                    sprite: Sprite::new(Vec2::new(DUST_SIZE, DUST_SIZE)),
+                    transform: Transform {
+                        translation: feet,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(Particle {
+                    lifetime: Timer::from_seconds(DUST_LIFETIME, false)
+                })
+                .insert(Pooled{active: true})
+                .insert(Velocity(velocity));
+        }
+    }
+}
+
+fn particles_update(
+    time: Res<Time>,
+    mut pool: ResMut<Pool<Particle>>,
+    mut query: Query<(Entity, &mut Particle, &mut Pooled, &mut Visible, &mut Transform, &Velocity)>
+){
+    for (entity, mut particle, mut pooled, mut visible, mut transform, velocity) in query.iter_mut() {
+        if !pooled.active {continue;}
+
+        particle.lifetime.tick(time.delta());
+        if particle.lifetime.finished() {
+            pool.release(entity, &mut pooled, &mut visible);
+            continue;
+        }
+
+        //Grow while drifting away
+        let grow = 1. + particle.lifetime.percent();
+        transform.scale = Vec3::new(grow, grow, 1.);
+        transform.translation.x += velocity.0.x * TIME_STEP;
+        transform.translation.y += velocity.0.y * TIME_STEP;
+    }
+}
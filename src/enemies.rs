@@ -1,39 +1,145 @@
 use bevy::prelude::*;
+use bevy::diagnostic::DiagnosticId;
 use physics::*;
 use player::*;
-use gamestate::{GameState};
+use pool::*;
+use culling::*;
+use animation::*;
+use sheets::*;
+use gamestate::{GameState, GameMode};
 
 use rand::{thread_rng, Rng};
+use serde::Deserialize;
+
+use crate::{Materials};
 
-use crate::{Materials, TIME_STEP};
-use crate::{Animation, LoopAnim};
 use crate::physics;
 use crate::player;
+use crate::pool;
+use crate::culling;
+use crate::animation;
+use crate::sheets;
 use crate::gamestate;
 
+// region:    Constants
+const ENEMY_POOL_DIAGNOSTICS: [DiagnosticId; 3] = [
+    DiagnosticId::from_u128(0x6b1e2c3e_5d7a_4e0f_9a41_2f3c8d1b7e01),
+    DiagnosticId::from_u128(0x6b1e2c3e_5d7a_4e0f_9a41_2f3c8d1b7e02),
+    DiagnosticId::from_u128(0x6b1e2c3e_5d7a_4e0f_9a41_2f3c8d1b7e03),
+];
+const ENEMY_SCALE: f32 = 0.20;
+const LANES: [f32; 2] = [-100., 35.];
+const START_DIFFICULTY: f32 = 3.;
+//Difficulty drop between two tiers
+const TIER_STEP: f32 = 0.75;
+//Blue bees fly this much faster than red ones
+const BLUE_SPEED_FACTOR: f32 = 1.4;
+// endregion:    Constants
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum EnemyKind {
+    Red,
+    Blue
+}
+
+impl EnemyKind {
+    fn atlas(&self, materials: &Materials) -> Handle<TextureAtlas> {
+        match self {
+            EnemyKind::Red => materials.enemies.clone(),
+            EnemyKind::Blue => materials.blue_enemies.clone()
+        }
+    }
+
+    pub fn sheet(&self, materials: &Materials) -> Handle<SpriteSheetMeta> {
+        match self {
+            EnemyKind::Red => materials.enemies_sheet.clone(),
+            EnemyKind::Blue => materials.blue_enemies_sheet.clone()
+        }
+    }
+}
+
 // region:    Resources
-struct SpawnTimer {
+pub struct SpawnTimer {
     pub timer: Timer,
     //Time to add to spawn
     difficulty: f32
 }
 
-struct EnemySpawnSettings {
+impl SpawnTimer {
+    //Starts at 1 and goes up as the extra spawn time runs out
+    pub fn tier(&self) -> u32 {
+        1 + ((START_DIFFICULTY - self.difficulty) / TIER_STEP) as u32
+    }
+
+    //0 at the start of a run, 1 once the extra spawn time is gone
+    pub fn intensity(&self) -> f32 {
+        1. - self.difficulty / START_DIFFICULTY
+    }
+}
+
+pub struct EnemySpawnSettings {
     pub min_time: f32,
     pub max_time: f32,
     pub speed: f32,
 }
+
+impl EnemySpawnSettings {
+    //Pixels per second covered by a bee of this kind
+    pub fn speed_of(&self, kind: EnemyKind) -> f32 {
+        let factor = match kind {
+            EnemyKind::Red => 1.,
+            EnemyKind::Blue => BLUE_SPEED_FACTOR
+        };
+        self.speed.abs() * factor
+    }
+}
+
+//Keeps track of lane usage so other spawners can pick safe paths
+pub struct SpawnDirector {
+    last_lane: usize
+}
+
+impl SpawnDirector {
+    pub fn lane_y(lane: usize) -> f32 {
+        LANES[lane]
+    }
+
+    pub fn lane_count() -> usize {
+        LANES.len()
+    }
+
+    //Lane the most recent enemy did not take
+    pub fn safe_lane(&self) -> usize {
+        (self.last_lane + 1) % LANES.len()
+    }
+}
 // endregion:    Resources
 
 // region:    Components
-pub struct Enemy;
+pub struct Enemy {
+    pub kind: EnemyKind,
+    //Set once the bee has gone past the player
+    pub passed: bool,
+    //Set when the bee got close to the player without touching her
+    pub near_miss: bool
+}
 // endregion:    Components
 
+// region:    Events
+//Asks for a bee in a lane, whatever decided it should come
+pub struct SpawnEnemy {
+    pub lane: usize,
+    pub kind: EnemyKind
+}
+// endregion:    Events
+
 // region:    Plugin
 pub struct EnemiesPlugin;
 impl Plugin for EnemiesPlugin{
     fn build(&self, app: &mut AppBuilder){
         app
+            .add_plugin(PoolPlugin::<Enemy>::new("enemy_pool", ENEMY_POOL_DIAGNOSTICS))
+            .add_event::<SpawnEnemy>()
             .add_system_set(
                 SystemSet::on_enter(GameState::Playing)
                     .with_system(reset_spawn_timer.system())
@@ -41,6 +147,7 @@ impl Plugin for EnemiesPlugin{
             .add_system_set(
                 SystemSet::on_update(GameState::Playing)
                     .with_system(enemy_spawn.system())
+                    .with_system(spawn_enemies.system())
                     .with_system(enemies_movement.system())
                     .with_system(enemies_offscreen.system())
                     .with_system(difficulty_setter.system())
@@ -57,28 +164,32 @@ impl Plugin for EnemiesPlugin{
             )
             .insert_resource( SpawnTimer {
                 timer: Timer::from_seconds(2.5, true),
-                difficulty: 3.
+                difficulty: START_DIFFICULTY
             })
             .insert_resource( EnemySpawnSettings {
                 min_time: 1.5,
                 max_time: 4.,
                 speed: -170.0
+            })
+            .insert_resource( SpawnDirector {
+                last_lane: 0
             });
     }
 }
 // endregion:    Plugin
 
+//Endless mode, spawns a bee whenever the timer runs out
 fn enemy_spawn(
-    mut commands: Commands,
     enemy_settings: Res<EnemySpawnSettings>,
     time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mode: Res<GameMode>,
     mut spawn_timer: ResMut<SpawnTimer>,
-    materials: Res<Materials>,
-    player_alive: Res<PlayerAlive>
+    mut spawns: EventWriter<SpawnEnemy>
 ){
-    if !player_alive.0 {return;}
-    
-    spawn_timer.timer.tick(time.delta());
+    if *mode != GameMode::Endless {return;}
+
+    spawn_timer.timer.tick(time.delta().mul_f32(time_scale.0));
     if !spawn_timer.timer.finished() {
         return;
     }
@@ -87,34 +198,97 @@ fn enemy_spawn(
     let random_time = rng.gen_range(enemy_settings.min_time..enemy_settings.max_time);
     spawn_timer.timer = Timer::from_seconds(random_time+spawn_timer.difficulty, true);
 
-    let height = rng.gen_range(0.0..1.5) as i32;
-    
-    commands
-        .spawn_bundle(SpriteSheetBundle  {
-            texture_atlas: materials.enemies.clone(),
-            transform: Transform{
-                translation: Vec3::new( 500., -100.+ 135.* height as f32, 15.),
-                scale: Vec3::new(0.20, 0.20, 1.),
+    let lane = rng.gen_range(0.0..1.5) as usize;
+    spawns.send(SpawnEnemy { lane, kind: EnemyKind::Red });
+}
+
+//Builds the bees asked for by any mode, recycling parked ones first
+fn spawn_enemies(
+    mut commands: Commands,
+    enemy_settings: Res<EnemySpawnSettings>,
+    mut director: ResMut<SpawnDirector>,
+    mut pool: ResMut<Pool<Enemy>>,
+    materials: Res<Materials>,
+    sheets: Res<Assets<SpriteSheetMeta>>,
+    view: Res<ViewRect>,
+    player_alive: Res<PlayerAlive>,
+    mut spawns: EventReader<SpawnEnemy>,
+    mut pooled_query: Query<(&mut Enemy, &mut Pooled, &mut Visible, &mut Transform, &mut Velocity,
+        &mut Animation, &mut Handle<TextureAtlas>, &mut Sheet)>
+){
+    for spawn in spawns.iter() {
+        if !player_alive.0 {continue;}
+
+        //Bees wait for their sheet to know their size and flight clip
+        let sheet = spawn.kind.sheet(&materials);
+        let meta = match sheets.get(&sheet) {
+            Some(meta) => meta,
+            None => continue
+        };
+
+        director.last_lane = spawn.lane;
+
+        let margin = spawn_margin(meta);
+        let translation = Vec3::new(spawn_x(&view, meta), SpawnDirector::lane_y(spawn.lane), 15.);
+        let velocity = Vec2::new(-enemy_settings.speed_of(spawn.kind), 0.);
+        let clip = meta.clip("fly").map(Animation::from).unwrap_or_default();
+
+        //Recycle a parked enemy before spawning a new one
+        if let Some(entity) = pool.acquire() {
+            if let Ok((mut enemy, mut pooled, mut visible, mut transform, mut vel, mut anim, mut atlas, mut enemy_sheet)) = pooled_query.get_mut(entity) {
+                *enemy = Enemy { kind: spawn.kind, passed: false, near_miss: false };
+                pooled.active = true;
+                visible.is_visible = true;
+                transform.translation = translation;
+                vel.0 = velocity;
+                *atlas = spawn.kind.atlas(&materials);
+                enemy_sheet.0 = sheet;
+                *anim = clip;
+                continue;
+            }
+        }
+
+        commands
+            .spawn_bundle(SpriteSheetBundle  {
+                texture_atlas: spawn.kind.atlas(&materials),
+                transform: Transform{
+                    translation,
+                    scale: Vec3::new(ENEMY_SCALE, ENEMY_SCALE, 1.),
+                    ..Default::default()
+                },
                 ..Default::default()
-            },
-            ..Default::default()
-        })
-        .insert(Enemy)
-        .insert(Timer::from_seconds(0.05, true))
-        .insert(Animation{index: 0,size: 13})
-        .insert(LoopAnim)
-        .insert(Velocity(Vec2::new(enemy_settings.speed, 0.)));
+            })
+            .insert(Enemy{kind: spawn.kind, passed: false, near_miss: false})
+            .insert(Pooled{active: true})
+            .insert(CullBounds{margin})
+            .insert(Sheet(sheet))
+            .insert(clip)
+            .insert(Velocity(velocity));
+    }
+}
+
+fn spawn_margin(meta: &SpriteSheetMeta) -> f32 {
+    meta.cell_size().x * ENEMY_SCALE / 2.
+}
+
+//Bees start just past the right edge of the view
+pub fn spawn_x(view: &ViewRect, meta: &SpriteSheetMeta) -> f32 {
+    view.max.x + spawn_margin(meta)
 }
 
+//Moves by frame time, so beatmap bees reach the player on the beat at any frame rate
 fn enemies_movement(
     time: Res<Time>,
-    mut query: Query<(&Velocity, &mut Transform, With<Enemy>)>
+    time_scale: Res<TimeScale>,
+    mut query: Query<(&Velocity, &mut Transform, &Pooled, With<Enemy>)>
 ){
-    for (velocity, mut transform, _) in query.iter_mut() {
+    for (velocity, mut transform, pooled, _) in query.iter_mut() {
+        if !pooled.active {continue;}
+
         let base: f32 = 10.;
         let delta_time = time.time_since_startup().as_secs() as f32 + time.time_since_startup().subsec_nanos() as f32 * f32::powi(base, -9);
         
-        transform.translation.x += velocity.0.x * TIME_STEP;
+        transform.translation.x += velocity.0.x * time_scale.0 * time.delta_seconds();
         let amplitude = 0.5;
         let frequency = 2.;
         transform.translation.y +=  amplitude * (frequency * delta_time).sin();
@@ -122,20 +296,25 @@ fn enemies_movement(
 }
 
 fn enemies_offscreen(
-    mut commands: Commands,
-    mut enemy_query: Query<(Entity, &Transform, With<Enemy>)>
+    mut pool: ResMut<Pool<Enemy>>,
+    mut offscreen: EventReader<Offscreen>,
+    mut enemy_query: Query<(&mut Pooled, &mut Visible, With<Enemy>)>
 ){
-    for (entity, enemy_tf, _) in enemy_query.iter_mut() {
-        if enemy_tf.translation.x <= -550. {
-                commands.entity(entity).despawn();
+    for Offscreen(entity) in offscreen.iter() {
+        if let Ok((mut pooled, mut visible, _)) = enemy_query.get_mut(*entity) {
+            pool.release(*entity, &mut pooled, &mut visible);
         }
     }
 }
 
 fn difficulty_setter(
     time: Res<Time>,
+    mode: Res<GameMode>,
     mut spawn_timer: ResMut<SpawnTimer>
 ){
+    //Beatmaps set their own pace
+    if *mode != GameMode::Endless {return;}
+
     spawn_timer.difficulty -= time.delta_seconds() as f32 * 0.05;
     spawn_timer.difficulty = spawn_timer.difficulty.max(0.);
 }
@@ -143,15 +322,15 @@ fn difficulty_setter(
 fn reset_spawn_timer(
     mut spawn_timer: ResMut<SpawnTimer>
 ){
-    spawn_timer.difficulty = 3.;
+    spawn_timer.difficulty = START_DIFFICULTY;
     spawn_timer.timer = Timer::from_seconds(2.5, true);
 }
 
 fn enemies_cleanup(
-    mut commands: Commands,
-    mut enemy_query: Query<(Entity, With<Enemy>)>
+    mut pool: ResMut<Pool<Enemy>>,
+    mut enemy_query: Query<(Entity, &mut Pooled, &mut Visible, With<Enemy>)>
 ){
-    for (entity, _) in enemy_query.iter_mut() {
-        commands.entity(entity).despawn();
+    for (entity, mut pooled, mut visible, _) in enemy_query.iter_mut() {
+        pool.release(entity, &mut pooled, &mut visible);
     }
 }
\ No newline at end of file
@@ -1,20 +1,100 @@
 use bevy::prelude::*;
+use audio::*;
+use controls::*;
+
+use crate::audio;
+use crate::controls;
 
 // region:    State
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum GameState {
+    Loading,
     MainMenu,
+    Shop,
+    Settings,
+    Calibration,
     Playing,
+    Paused,
     Dead
 }
+
+impl GameState {
+    //Screens over the world, the player holds still behind them
+    pub fn freezes_world(&self) -> bool {
+        match self {
+            GameState::Loading | GameState::Shop | GameState::Settings | GameState::Calibration | GameState::Paused => true,
+            GameState::MainMenu | GameState::Playing | GameState::Dead => false
+        }
+    }
+}
+
+//What drives the spawns of a run
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum GameMode {
+    Endless,
+    Rhythm
+}
+
+//Entries of the main menu, top to bottom
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MenuItem {
+    Play,
+    Rhythm,
+    Shop,
+    Settings,
+    Calibrate
+}
+
+impl MenuItem {
+    pub const ALL: [MenuItem; 5] = [
+        MenuItem::Play,
+        MenuItem::Rhythm,
+        MenuItem::Shop,
+        MenuItem::Settings,
+        MenuItem::Calibrate
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MenuItem::Play => "Play",
+            MenuItem::Rhythm => "<R> Rhythm mode",
+            MenuItem::Shop => "<S> Shop",
+            MenuItem::Settings => "<O> Settings",
+            MenuItem::Calibrate => "<C> Calibrate rhythm"
+        }
+    }
+
+    //Play is reached through the confirm action only
+    fn shortcut(&self) -> Option<KeyCode> {
+        match self {
+            MenuItem::Play => None,
+            MenuItem::Rhythm => Some(KeyCode::R),
+            MenuItem::Shop => Some(KeyCode::S),
+            MenuItem::Settings => Some(KeyCode::O),
+            MenuItem::Calibrate => Some(KeyCode::C)
+        }
+    }
+}
 // endregion:    State
 
+// region:    Resources
+//Highlighted main menu entry
+pub struct MenuCursor(pub usize);
+// endregion:    Resources
+
+// region:    Components
+//Main menu entry that can be hovered and clicked
+pub struct MenuButton(pub MenuItem);
+// endregion:    Components
+
 // region:    Plugin
 pub struct GameStatePlugin;
 impl Plugin for GameStatePlugin {
     fn build(&self, app: &mut AppBuilder) {
         app
-            .add_state(GameState::MainMenu)
+            .add_state(GameState::Loading)
+            .insert_resource(GameMode::Endless)
+            .insert_resource(MenuCursor(0))
             .add_system(handle_gamestate.system());
     }
 }
@@ -22,22 +102,138 @@ impl Plugin for GameStatePlugin {
 
 fn handle_gamestate(
     mut game_state: ResMut<State<GameState>>,
-    keyboard_input: Res<Input<KeyCode>>
+    mut mode: ResMut<GameMode>,
+    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<Actions>,
+    mut cursor: ResMut<MenuCursor>,
+    mut sfx: EventWriter<PlaySfx>,
+    button_query: Query<(&Interaction, &MenuButton), Changed<Interaction>>
 ){
     match game_state.current() {
+        //Leaves on its own once every asset is in
+        GameState::Loading => {}
         GameState::MainMenu => {
-            if keyboard_input.pressed(KeyCode::Return) {
-                match game_state.set(GameState::Playing) {
-                    Ok(_) => {println!("Playing State");}
+            let count = MenuItem::ALL.len();
+            if actions.just_pressed(Action::MenuUp) {
+                cursor.0 = (cursor.0 + count - 1) % count;
+                sfx.send(PlaySfx(SfxKind::MenuMove));
+            }
+            if actions.just_pressed(Action::MenuDown) {
+                cursor.0 = (cursor.0 + 1) % count;
+                sfx.send(PlaySfx(SfxKind::MenuMove));
+            }
+
+            //The pointer highlights what it hovers
+            let mut clicked = None;
+            for (interaction, button) in button_query.iter() {
+                let index = MenuItem::ALL.iter().position(|item| *item == button.0).unwrap_or(0);
+                match interaction {
+                    Interaction::Hovered => {
+                        if cursor.0 != index {
+                            cursor.0 = index;
+                            sfx.send(PlaySfx(SfxKind::MenuMove));
+                        }
+                    }
+                    Interaction::Clicked => clicked = Some(button.0),
+                    Interaction::None => {}
+                }
+            }
+
+            //The clicked or highlighted item, or any item through its shortcut
+            let chosen = if clicked.is_some() {
+                clicked
+            } else if actions.just_pressed(Action::Confirm) {
+                Some(MenuItem::ALL[cursor.0])
+            } else {
+                MenuItem::ALL.iter().copied().find(|item| match item.shortcut() {
+                    Some(key) => keyboard_input.just_pressed(key),
+                    None => false
+                })
+            };
+
+            match chosen {
+                Some(MenuItem::Play) => {
+                    match game_state.set(GameState::Playing) {
+                        Ok(_) => {
+                            println!("Playing State");
+                            *mode = GameMode::Endless;
+                            sfx.send(PlaySfx(SfxKind::MenuSelect));
+                        }
+                        Err(_) => {}
+                    }
+                }
+                Some(MenuItem::Rhythm) => {
+                    match game_state.set(GameState::Playing) {
+                        Ok(_) => {
+                            println!("Playing State (rhythm)");
+                            *mode = GameMode::Rhythm;
+                            sfx.send(PlaySfx(SfxKind::MenuSelect));
+                        }
+                        Err(_) => {}
+                    }
+                }
+                Some(MenuItem::Shop) => {
+                    match game_state.push(GameState::Shop) {
+                        Ok(_) => {
+                            println!("Shop State");
+                            sfx.send(PlaySfx(SfxKind::MenuSelect));
+                        }
+                        Err(_) => {}
+                    }
+                }
+                Some(MenuItem::Settings) => {
+                    match game_state.push(GameState::Settings) {
+                        Ok(_) => {
+                            println!("Settings State");
+                            sfx.send(PlaySfx(SfxKind::MenuSelect));
+                        }
+                        Err(_) => {}
+                    }
+                }
+                Some(MenuItem::Calibrate) => {
+                    match game_state.push(GameState::Calibration) {
+                        Ok(_) => {
+                            println!("Calibration State");
+                            sfx.send(PlaySfx(SfxKind::MenuSelect));
+                        }
+                        Err(_) => {}
+                    }
+                }
+                None => {}
+            }
+        }
+        GameState::Shop | GameState::Settings | GameState::Calibration => {
+            if actions.just_pressed(Action::Back) {
+                match game_state.pop() {
+                    Ok(_) => {println!("MainMenu State");}
                     Err(_) => {}
                 }
             }
         }
         GameState::Playing => {
-            //Create pause mode?
+            if actions.just_pressed(Action::Pause) {
+                match game_state.push(GameState::Paused) {
+                    Ok(_) => {
+                        println!("Paused State");
+                        sfx.send(PlaySfx(SfxKind::MenuSelect));
+                    }
+                    Err(_) => {}
+                }
+            }
+        }
+        GameState::Paused => {
+            if actions.just_pressed(Action::Pause) || actions.just_pressed(Action::Back) {
+                match game_state.pop() {
+                    Ok(_) => {
+                        println!("Playing State");
+                        sfx.send(PlaySfx(SfxKind::MenuSelect));
+                    }
+                    Err(_) => {}
+                }
+            }
         }
         GameState::Dead => {
-            if keyboard_input.pressed(KeyCode::Escape) {
+            if actions.pressed(Action::Back) {
                 match game_state.set(GameState::Playing) {
                     Ok(_) => {println!("Playing State");}
                     Err(_) => {}
@@ -0,0 +1,363 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use player::*;
+use enemies::*;
+use collectibles::*;
+use powerups::*;
+use score::*;
+use leaderboard::*;
+use settings::*;
+use tween::*;
+use layout::*;
+use controls::*;
+use gamestate::{GameState};
+
+use crate::{Materials};
+use crate::player;
+use crate::enemies;
+use crate::collectibles;
+use crate::powerups;
+use crate::score;
+use crate::leaderboard;
+use crate::settings;
+use crate::tween;
+use crate::layout;
+use crate::controls;
+use crate::gamestate;
+
+// region:    Constants
+const HUD_FONT_SIZE: f32 = 50.;
+const SMALL_FONT_SIZE: f32 = 34.;
+const PUNCH_FONT_SIZE: f32 = 64.;
+const PUNCH_DURATION: f32 = 0.3;
+// endregion:    Constants
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HudItem {
+    Score,
+    Best,
+    Combo,
+    Candy,
+    PowerUps,
+    Difficulty
+}
+
+impl HudItem {
+    pub const ALL: [HudItem; 6] = [
+        HudItem::Score,
+        HudItem::Best,
+        HudItem::Combo,
+        HudItem::Candy,
+        HudItem::PowerUps,
+        HudItem::Difficulty
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HudItem::Score => "Score",
+            HudItem::Best => "Best score",
+            HudItem::Combo => "Combo",
+            HudItem::Candy => "Candy",
+            HudItem::PowerUps => "Power-ups",
+            HudItem::Difficulty => "Difficulty"
+        }
+    }
+}
+
+// region:    Components
+struct HudRoot;
+struct PauseRoot;
+struct RetryRoot;
+//Element hidden when its item is turned off in the settings
+struct HudElement(HudItem);
+struct ScoreText;
+struct BestText;
+struct ComboText;
+struct CandyText;
+struct PowerUpText;
+struct DifficultyText;
+// endregion:    Components
+
+// region:    Plugin
+pub struct HudPlugin;
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(hud_spawn.system())
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(update_score_text.system())
+                    .with_system(update_best_text.system())
+                    .with_system(update_combo_text.system())
+                    .with_system(update_candy_text.system())
+                    .with_system(update_powerup_text.system())
+                    .with_system(update_difficulty_text.system())
+                    .with_system(score_punch.system())
+                    .with_system(apply_hud_settings.system())
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::Paused)
+                    .with_system(pause_spawn.system())
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Paused)
+                    .with_system(despawn_screen::<PauseRoot>.system())
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::Dead)
+                    .with_system(retry_spawn.system())
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Dead)
+                    .with_system(despawn_screen::<HudRoot>.system())
+                    .with_system(despawn_screen::<RetryRoot>.system())
+            );
+    }
+}
+// endregion:    Plugin
+
+fn hud_spawn(
+    mut commands: Commands,
+    materials: Res<Materials>,
+    ui: Res<UiMaterials>
+) {
+    let style = TextStyle {
+        font: materials.font.clone(),
+        font_size: HUD_FONT_SIZE,
+        color: TEXT_COLOR,
+    };
+    let small = TextStyle { font_size: SMALL_FONT_SIZE, ..style.clone() };
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                ..Default::default()
+            },
+            material: ui.clear.clone(),
+            ..Default::default()
+        })
+        .insert(HudRoot)
+        .with_children(|hud| {
+            //Run progress in the top left corner
+            hud
+                .spawn_bundle(anchored(&ui, Rect {
+                    left: Val::Px(20.),
+                    top: Val::Px(10.),
+                    ..Default::default()
+                }, AlignItems::FlexStart))
+                .with_children(|column| {
+                    column.spawn_bundle(label_value(&style, "Score: ", "00000"))
+                        .insert(HudElement(HudItem::Score))
+                        .insert(ScoreText);
+                    column.spawn_bundle(label_value(&small, "Best: ", ""))
+                        .insert(HudElement(HudItem::Best))
+                        .insert(BestText);
+                    column.spawn_bundle(label_value(&style, "Candy: ", "0"))
+                        .insert(HudElement(HudItem::Candy))
+                        .insert(CandyText);
+                    column.spawn_bundle(label_value(&style, "Combo: ", "x1.00"))
+                        .insert(HudElement(HudItem::Combo))
+                        .insert(ComboText);
+                });
+            //Difficulty and power-ups in the top right corner
+            hud
+                .spawn_bundle(anchored(&ui, Rect {
+                    right: Val::Px(20.),
+                    top: Val::Px(10.),
+                    ..Default::default()
+                }, AlignItems::FlexEnd))
+                .with_children(|column| {
+                    column.spawn_bundle(label_value(&small, "Tier ", "1"))
+                        .insert(HudElement(HudItem::Difficulty))
+                        .insert(DifficultyText);
+                    column.spawn_bundle(label(vec![TextSection {
+                            value: String::new(),
+                            style: small.clone()
+                        }]))
+                        .insert(HudElement(HudItem::PowerUps))
+                        .insert(PowerUpText);
+                });
+            //Pointer way to pause, retry takes the other bottom corner
+            hud
+                .spawn_bundle(anchored(&ui, Rect {
+                    right: Val::Px(20.),
+                    bottom: Val::Px(20.),
+                    ..Default::default()
+                }, AlignItems::FlexEnd))
+                .with_children(|corner| {
+                    corner.spawn_bundle(label(vec![TextSection {
+                            value: "II Pause".to_string(),
+                            style: small.clone()
+                        }]))
+                        .insert(ClickAction(Action::Pause))
+                        .insert(Interaction::default());
+                });
+        });
+}
+
+fn pause_spawn(
+    mut commands: Commands,
+    materials: Res<Materials>,
+    ui: Res<UiMaterials>
+){
+    let style = TextStyle {
+        font: materials.font.clone(),
+        font_size: 80.0,
+        color: TEXT_COLOR,
+    };
+
+    commands
+        .spawn_bundle(centered(&ui))
+        .insert(PauseRoot)
+        .with_children(|screen| {
+            screen.spawn_bundle(label(vec![TextSection {
+                value: "Paused".to_string(),
+                style: style.clone()
+            }]));
+            screen.spawn_bundle(label(vec![TextSection {
+                value: "Pause or back to resume".to_string(),
+                style: TextStyle { font_size: 40.0, ..style.clone() }
+            }]))
+                .insert(ClickAction(Action::Pause))
+                .insert(Interaction::default());
+        });
+}
+
+//Pointer way back into a run, the keys still work too
+fn retry_spawn(
+    mut commands: Commands,
+    materials: Res<Materials>,
+    ui: Res<UiMaterials>
+){
+    commands
+        .spawn_bundle(anchored(&ui, Rect {
+            bottom: Val::Px(20.),
+            left: Val::Px(20.),
+            ..Default::default()
+        }, AlignItems::FlexStart))
+        .insert(RetryRoot)
+        .with_children(|corner| {
+            corner.spawn_bundle(label(vec![TextSection {
+                value: "Retry >".to_string(),
+                style: TextStyle {
+                    font: materials.font.clone(),
+                    font_size: 40.0,
+                    color: TEXT_COLOR,
+                }
+            }]))
+                .insert(ClickAction(Action::Back))
+                .insert(Interaction::default());
+        });
+}
+
+fn apply_hud_settings(
+    settings: Res<Settings>,
+    mut query: Query<(&HudElement, &mut Style)>
+){
+    for (element, mut style) in query.iter_mut() {
+        let display = if settings.shows(element.0) { Display::Flex } else { Display::None };
+        if style.display != display {
+            style.display = display;
+        }
+    }
+}
+
+fn update_score_text(
+    score: Res<Score>,
+    mut query: Query<(&mut Text, With<ScoreText>)>
+){
+    if !score.is_changed() {return;}
+
+    if let Ok((mut text, _)) = query.single_mut() {
+        text.sections[1].value = format!("{:05}", score.points as u32);
+    }
+}
+
+//Bonuses punch the score, distance alone does not
+fn score_punch(
+    mut commands: Commands,
+    mut events: EventReader<ScoreEvent>,
+    query: Query<(Entity, With<ScoreText>)>
+){
+    if !events.iter().any(|event| event.bonus != Bonus::Hit) {return;}
+
+    if let Ok((entity, _)) = query.single() {
+        commands.entity(entity).insert(Tween::new(
+            FontSizeLens { from: PUNCH_FONT_SIZE, to: HUD_FONT_SIZE },
+            PUNCH_DURATION,
+            Ease::BackOut
+        ));
+    }
+}
+
+//Session and all-time bests, the current run counts as soon as it beats them
+fn update_best_text(
+    score: Res<Score>,
+    session: Res<SessionBest>,
+    scores: Res<HighScores>,
+    mut query: Query<(&mut Text, With<BestText>)>
+){
+    if let Ok((mut text, _)) = query.single_mut() {
+        let current = score.points as u32;
+        let session_best = session.0.max(current);
+        let all_time = scores.best().max(session_best);
+        let value = format!("{:05} / {:05}", session_best, all_time);
+        if text.sections[1].value != value {
+            text.sections[1].value = value;
+        }
+    }
+}
+
+fn update_combo_text(
+    score: Res<Score>,
+    mut query: Query<(&mut Text, With<ComboText>)>
+){
+    if !score.is_changed() {return;}
+
+    if let Ok((mut text, _)) = query.single_mut() {
+        text.sections[1].value = format!("x{:.2}", score.combo());
+    }
+}
+
+fn update_candy_text(
+    count: Res<CandyCount>,
+    mut query: Query<(&mut Text, With<CandyText>)>
+){
+    if !count.is_changed() {return;}
+
+    if let Ok((mut text, _)) = query.single_mut() {
+        text.sections[1].value = count.0.to_string();
+    }
+}
+
+fn update_powerup_text(
+    powerups: Res<ActivePowerUps>,
+    mut query: Query<(&mut Text, With<PowerUpText>)>
+){
+    if let Ok((mut text, _)) = query.single_mut() {
+        let lines: Vec<String> = powerups.iter()
+            .map(|(kind, remaining)| match kind {
+                PowerUpKind::ScoreMultiplier => format!("{} x{} {:.1}s", kind.label(), powerups.score_multiplier(), remaining),
+                _ => format!("{} {:.1}s", kind.label(), remaining)
+            })
+            .collect();
+
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+fn update_difficulty_text(
+    spawn_timer: Res<SpawnTimer>,
+    mut query: Query<(&mut Text, With<DifficultyText>)>
+){
+    if let Ok((mut text, _)) = query.single_mut() {
+        let value = spawn_timer.tier().to_string();
+        if text.sections[1].value != value {
+            text.sections[1].value = value;
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use bevy::prelude::*;
+use bevy::ecs::component::Component;
+use bevy::ui::FocusPolicy;
+
+// region:    Constants
+//Plain text on every screen
+pub const TEXT_COLOR: Color = Color::rgb(0.0823, 0.0627, 0.1686);
+//Selected entries, callouts and anything else that should catch the eye
+pub const HIGHLIGHT_COLOR: Color = Color::rgb(0.85, 0.25, 0.55);
+// endregion:    Constants
+
+// region:    Resources
+pub struct UiMaterials {
+    pub clear: Handle<ColorMaterial>
+}
+// endregion:    Resources
+
+// region:    Plugin
+pub struct LayoutPlugin;
+impl Plugin for LayoutPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .add_startup_system(layout_setup.system())
+            .add_system(pass_through_containers.system());
+    }
+}
+// endregion:    Plugin
+
+fn layout_setup(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>
+){
+    commands.spawn_bundle(UiCameraBundle::default());
+    commands.insert_resource(UiMaterials {
+        clear: materials.add(Color::NONE.into())
+    });
+}
+
+//Nodes without a policy block clicks, containers let them through to other screens
+fn pass_through_containers(
+    mut commands: Commands,
+    query: Query<Entity, (Added<Node>, Without<FocusPolicy>)>
+){
+    for entity in query.iter() {
+        commands.entity(entity).insert(FocusPolicy::Pass);
+    }
+}
+
+//Invisible column pinned to the given window edges, children stack top to bottom
+pub fn anchored(materials: &UiMaterials, position: Rect<Val>, align: AlignItems) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position,
+            flex_direction: FlexDirection::ColumnReverse,
+            align_items: align,
+            ..Default::default()
+        },
+        material: materials.clear.clone(),
+        ..Default::default()
+    }
+}
+
+//Invisible column covering the window with its children in the middle
+pub fn centered(materials: &UiMaterials) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        material: materials.clear.clone(),
+        ..Default::default()
+    }
+}
+
+pub fn label(sections: Vec<TextSection>) -> TextBundle {
+    TextBundle {
+        text: Text {
+            sections,
+            alignment: TextAlignment {
+                vertical: VerticalAlign::Center,
+                horizontal: HorizontalAlign::Center,
+            }
+        },
+        ..Default::default()
+    }
+}
+
+//Fixed label in the first section, value updated in the second
+pub fn label_value(style: &TextStyle, label_text: &str, value: &str) -> TextBundle {
+    label(vec![
+        TextSection { value: label_text.to_string(), style: style.clone() },
+        TextSection { value: value.to_string(), style: style.clone() }
+    ])
+}
+
+//Removes a screen through its tagged root, leaving every other entity alone
+pub fn despawn_screen<T: Component>(
+    mut commands: Commands,
+    query: Query<Entity, With<T>>
+){
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,245 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use score::*;
+use layout::*;
+use toasts::*;
+use controls::*;
+use gamestate::{GameState};
+
+use crate::{Materials};
+use crate::score;
+use crate::layout;
+use crate::toasts;
+use crate::controls;
+use crate::gamestate;
+use crate::storage;
+
+// region:    Constants
+const SCORES_FILE: &str = "scores.ron";
+const MAX_ENTRIES: usize = 5;
+const DEFAULT_NAME: &str = "Player";
+const MAX_NAME_LEN: usize = 10;
+// endregion:    Constants
+
+// region:    Resources
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub score: u32
+}
+
+//Local high-score table, best first
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+    //Offered again on the next name entry
+    pub last_name: String
+}
+
+impl HighScores {
+    pub fn best(&self) -> u32 {
+        self.entries.first().map(|entry| entry.score).unwrap_or(0)
+    }
+
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0 && (self.entries.len() < MAX_ENTRIES || self.entries.iter().any(|entry| score > entry.score))
+    }
+
+    //Adds the score in rank order, returns its rank when it made the table
+    pub fn insert(&mut self, name: &str, score: u32) -> Option<usize> {
+        let rank = self.entries.iter()
+            .position(|entry| score > entry.score)
+            .unwrap_or(self.entries.len());
+        if rank >= MAX_ENTRIES {return None;}
+
+        self.entries.insert(rank, HighScore { name: name.to_string(), score });
+        self.entries.truncate(MAX_ENTRIES);
+        Some(rank)
+    }
+
+    pub fn save(&self) {
+        storage::save(SCORES_FILE, self);
+    }
+}
+
+//Best score since the game was started
+pub struct SessionBest(pub u32);
+
+//Name typed for a run that made the table
+#[derive(Default)]
+struct NameEntry {
+    active: bool,
+    name: String
+}
+// endregion:    Resources
+
+// region:    Components
+struct NameEntryRoot;
+struct NameText;
+// endregion:    Components
+
+// region:    Plugin
+pub struct LeaderboardPlugin;
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .add_system_set(
+                SystemSet::on_enter(GameState::Dead)
+                    .with_system(start_name_entry.system())
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Dead)
+                    .with_system(name_entry_input.system())
+                    .with_system(update_name_text.system())
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Dead)
+                    .with_system(finish_name_entry.system())
+            )
+            .insert_resource(SessionBest(0))
+            .insert_resource(NameEntry::default())
+            .insert_resource(storage::load::<HighScores>(SCORES_FILE));
+    }
+}
+// endregion:    Plugin
+
+fn start_name_entry(
+    mut commands: Commands,
+    materials: Res<Materials>,
+    ui: Res<UiMaterials>,
+    score: Res<Score>,
+    scores: Res<HighScores>,
+    mut session: ResMut<SessionBest>,
+    mut entry: ResMut<NameEntry>
+){
+    let points = score.points as u32;
+    session.0 = session.0.max(points);
+    if !scores.qualifies(points) {return;}
+
+    entry.active = true;
+    entry.name = sanitize_name(&scores.last_name);
+
+    let style = TextStyle {
+        font: materials.font.clone(),
+        font_size: 50.0,
+        color: TEXT_COLOR,
+    };
+
+    commands
+        .spawn_bundle(centered(&ui))
+        .insert(NameEntryRoot)
+        .with_children(|screen| {
+            screen.spawn_bundle(label(vec![TextSection {
+                value: "New record! Enter your name".to_string(),
+                style: style.clone()
+            }]));
+            screen.spawn_bundle(label(vec![TextSection {
+                value: String::new(),
+                style: TextStyle { font_size: 70.0, color: HIGHLIGHT_COLOR, ..style.clone() }
+            }])).insert(NameText);
+            screen.spawn_bundle(label(vec![TextSection {
+                value: "<Enter> Save".to_string(),
+                style: TextStyle { font_size: 34.0, ..style.clone() }
+            }]))
+                .insert(ClickAction(Action::Confirm))
+                .insert(Interaction::default());
+        });
+}
+
+fn name_entry_input(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<Actions>,
+    mut characters: EventReader<ReceivedCharacter>,
+    score: Res<Score>,
+    mut scores: ResMut<HighScores>,
+    mut entry: ResMut<NameEntry>,
+    mut toasts: EventWriter<Toast>,
+    root_query: Query<Entity, With<NameEntryRoot>>
+){
+    if !entry.active {
+        characters.iter().for_each(drop);
+        return;
+    }
+
+    for event in characters.iter() {
+        if entry.name.chars().count() >= MAX_NAME_LEN {break;}
+        if is_name_char(event.char) {
+            entry.name.push(event.char);
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        entry.name.pop();
+    }
+
+    if actions.just_pressed(Action::Confirm) {
+        save_entry(&mut entry, &mut scores, &score, &mut toasts);
+        for root in root_query.iter() {
+            commands.entity(root).despawn_recursive();
+        }
+    }
+}
+
+fn update_name_text(
+    entry: Res<NameEntry>,
+    mut query: Query<(&mut Text, With<NameText>)>
+){
+    if !entry.is_changed() {return;}
+
+    if let Ok((mut text, _)) = query.single_mut() {
+        text.sections[0].value = format!("{}_", entry.name);
+    }
+}
+
+//Leaving the screen without confirming still keeps the record
+fn finish_name_entry(
+    mut commands: Commands,
+    score: Res<Score>,
+    mut scores: ResMut<HighScores>,
+    mut entry: ResMut<NameEntry>,
+    mut toasts: EventWriter<Toast>,
+    root_query: Query<Entity, With<NameEntryRoot>>
+){
+    if entry.active {
+        save_entry(&mut entry, &mut scores, &score, &mut toasts);
+    }
+    for root in root_query.iter() {
+        commands.entity(root).despawn_recursive();
+    }
+}
+
+fn save_entry(
+    entry: &mut NameEntry,
+    scores: &mut HighScores,
+    score: &Score,
+    toasts: &mut EventWriter<Toast>
+){
+    entry.active = false;
+
+    let name = sanitize_name(&entry.name);
+    if let Some(rank) = scores.insert(&name, score.points as u32) {
+        toasts.send(Toast(format!("#{} on the leaderboard!", rank + 1)));
+    }
+    scores.last_name = name;
+    scores.save();
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == ' ' || c == '-' || c == '_'
+}
+
+//Only plain characters are kept, anything left empty becomes the default name
+fn sanitize_name(name: &str) -> String {
+    let name: String = name.chars()
+        .filter(|c| is_name_char(*c))
+        .take(MAX_NAME_LEN)
+        .collect();
+    let name = name.trim();
+
+    if name.is_empty() {
+        DEFAULT_NAME.to_string()
+    } else {
+        name.to_string()
+    }
+}
@@ -0,0 +1,203 @@
+use bevy::prelude::*;
+use bevy::asset::{HandleId, LoadState};
+use layout::*;
+use audio::*;
+use skins::*;
+use sheets::*;
+use rhythm::*;
+use gamestate::{GameState};
+
+use crate::{Materials};
+use crate::layout;
+use crate::audio;
+use crate::skins;
+use crate::sheets;
+use crate::rhythm;
+use crate::gamestate;
+
+// region:    Constants
+const ERROR_COLOR: Color = Color::rgb(0.75, 0.1, 0.15);
+const BAR_COLOR: Color = Color::rgb(0.96, 0.45, 0.71);
+const BAR_BACK_COLOR: Color = Color::rgba(0.0823, 0.0627, 0.1686, 0.25);
+const BAR_WIDTH: f32 = 400.;
+const BAR_HEIGHT: f32 = 24.;
+// endregion:    Constants
+
+// region:    Resources
+//Set once a handle fails, the error screen then stays up
+#[derive(Default)]
+struct LoadingFailed(bool);
+// endregion:    Resources
+
+// region:    Components
+struct LoadingRoot;
+struct LoadingText;
+struct ProgressFill;
+// endregion:    Components
+
+// region:    Plugin
+pub struct LoadingPlugin;
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .add_system_set(
+                SystemSet::on_enter(GameState::Loading)
+                    .with_system(loading_spawn.system())
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Loading)
+                    .with_system(loading_progress.system())
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Loading)
+                    .with_system(despawn_screen::<LoadingRoot>.system())
+            )
+            .insert_resource(LoadingFailed::default());
+    }
+}
+// endregion:    Plugin
+
+//The bar is plain color so it shows before the font is in
+fn loading_spawn(
+    mut commands: Commands,
+    materials: Res<Materials>,
+    ui: Res<UiMaterials>,
+    mut colors: ResMut<Assets<ColorMaterial>>
+){
+    commands
+        .spawn_bundle(centered(&ui))
+        .insert(LoadingRoot)
+        .with_children(|screen| {
+            screen.spawn_bundle(label(vec![TextSection {
+                value: "Loading".to_string(),
+                style: TextStyle {
+                    font: materials.font.clone(),
+                    font_size: 40.0,
+                    color: TEXT_COLOR,
+                }
+            }])).insert(LoadingText);
+            screen
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(BAR_WIDTH), Val::Px(BAR_HEIGHT)),
+                        margin: Rect::all(Val::Px(10.)),
+                        ..Default::default()
+                    },
+                    material: colors.add(BAR_BACK_COLOR.into()),
+                    ..Default::default()
+                })
+                .with_children(|bar| {
+                    bar.spawn_bundle(NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Percent(0.), Val::Percent(100.)),
+                            ..Default::default()
+                        },
+                        material: colors.add(BAR_COLOR.into()),
+                        ..Default::default()
+                    }).insert(ProgressFill);
+                });
+        });
+}
+
+//Handles the first frames would draw with, sheet images and the rhythm track are only known once their file is read
+fn tracked_handles(
+    materials: &Materials,
+    sounds: &Sounds,
+    skins: &SkinRegistry,
+    sheets: &Assets<SpriteSheetMeta>,
+    rhythm: &RhythmAssets,
+    beatmaps: &Assets<Beatmap>
+) -> Vec<HandleId> {
+    let mut sheet_handles = vec![materials.enemies_sheet.clone(), materials.blue_enemies_sheet.clone()];
+    sheet_handles.extend(skins.sheets().map(|sheet| sheet.meta.clone()));
+
+    let mut handles = vec![
+        materials.enemies.id,
+        materials.blue_enemies.id,
+        materials.font.id,
+        materials.background.id,
+        rhythm.beatmap.id,
+        sounds.stinger.id,
+        sounds.buzz.id
+    ];
+    handles.extend(skins.sheets().map(|sheet| sheet.atlas.id));
+    handles.extend(sounds.sfx.values().map(|handle| handle.id));
+    handles.extend(sounds.music.values().map(|handle| handle.id));
+    handles.extend(sounds.stems.values().map(|handle| handle.id));
+    for sheet in sheet_handles.iter() {
+        handles.push(sheet.id);
+        if let Some(meta) = sheets.get(sheet) {
+            handles.push(HandleId::from(meta.image.as_str()));
+        }
+    }
+    if let Some(beatmap) = beatmaps.get(&rhythm.beatmap) {
+        handles.push(HandleId::from(beatmap.music.as_str()));
+    }
+    handles
+}
+
+fn loading_progress(
+    asset_server: Res<AssetServer>,
+    materials: Res<Materials>,
+    sounds: Res<Sounds>,
+    skins: Res<SkinRegistry>,
+    sheets: Res<Assets<SpriteSheetMeta>>,
+    rhythm: Res<RhythmAssets>,
+    beatmaps: Res<Assets<Beatmap>>,
+    mut windows: ResMut<Windows>,
+    mut colors: ResMut<Assets<ColorMaterial>>,
+    mut failed: ResMut<LoadingFailed>,
+    mut game_state: ResMut<State<GameState>>,
+    mut fill_query: Query<(&mut Style, &Handle<ColorMaterial>), With<ProgressFill>>,
+    mut text_query: Query<&mut Text, With<LoadingText>>
+){
+    if failed.0 {return;}
+
+    let handles = tracked_handles(&materials, &sounds, &skins, &sheets, &rhythm, &beatmaps);
+    let states: Vec<LoadState> = handles.iter()
+        .map(|handle| asset_server.get_load_state(*handle))
+        .collect();
+
+    let missing: Vec<String> = handles.iter().zip(states.iter())
+        .filter(|(_, state)| **state == LoadState::Failed)
+        .map(|(handle, _)| match asset_server.get_handle_path(*handle) {
+            Some(path) => path.path().display().to_string(),
+            None => format!("{:?}", handle)
+        })
+        .collect();
+
+    if !missing.is_empty() {
+        failed.0 = true;
+        eprintln!("Could not load: {}", missing.join(", "));
+
+        //The font may be what failed, so the bar and the title show it too
+        if let Ok((mut style, material)) = fill_query.single_mut() {
+            style.size.width = Val::Percent(100.);
+            if let Some(fill) = colors.get_mut(material) {
+                fill.color = ERROR_COLOR;
+            }
+        }
+        if let Some(window) = windows.get_primary_mut() {
+            let title = format!("{} - could not load {}", window.title(), missing.join(", "));
+            window.set_title(title);
+        }
+        if let Ok(mut text) = text_query.single_mut() {
+            text.sections[0].value = format!("Could not load\n{}", missing.join("\n"));
+            text.sections[0].style.font_size = 24.0;
+            text.sections[0].style.color = ERROR_COLOR;
+        }
+        return;
+    }
+
+    let loaded = states.iter().filter(|state| **state == LoadState::Loaded).count();
+    if let Ok((mut style, _)) = fill_query.single_mut() {
+        style.size.width = Val::Percent(100. * loaded as f32 / handles.len() as f32);
+    }
+
+    if loaded == handles.len() {
+        match game_state.set(GameState::MainMenu) {
+            Ok(_) => {println!("MainMenu State");}
+            Err(_) => {}
+        }
+    }
+}
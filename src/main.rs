@@ -3,39 +3,75 @@ mod physics;
 mod enemies;
 mod score;
 mod gamestate;
+mod pool;
+mod culling;
+mod storage;
+mod collectibles;
+mod powerups;
+mod skins;
+mod animation;
+mod effects;
+mod sheets;
+mod tween;
+mod layout;
+mod hud;
+mod leaderboard;
+mod settings;
+mod toasts;
+mod audio;
+mod rhythm;
+mod calibration;
+mod controls;
+mod loading;
 
 use bevy::prelude::*;
+#[cfg(debug_assertions)]
+use bevy::diagnostic::LogDiagnosticsPlugin;
 
 use player::*;
 use physics::*;
 use enemies::*;
 use score::*;
 use gamestate::*;
+use culling::*;
+use collectibles::*;
+use powerups::*;
+use skins::*;
+use animation::*;
+use effects::*;
+use sheets::*;
+use tween::*;
+use layout::*;
+use hud::*;
+use leaderboard::*;
+use settings::*;
+use toasts::*;
+use audio::*;
+use rhythm::*;
+use calibration::*;
+use controls::*;
+use loading::*;
 
 // region:    Constants
-const PLAYER_SPRITE_A: &str = "sprites/cute_girl_alive.png";
-const PLAYER_SPRITE_D: &str = "sprites/cute_girl_dead.png";
 const BACKGROUND_IMG: &str = "textures/background.png";
-const ENEMIES: &str = "sprites/enemies_red.png";
-//const ENEMIES: &str = "sprites/enemies_blue.png";
+const ENEMIES: &str = "sprites/enemies_red.sheet";
+const BLUE_ENEMIES: &str = "sprites/enemies_blue.sheet";
+const CANDY_COLOR: Color = Color::rgb(0.96, 0.45, 0.71);
 const CANDY_FONT: &str = "fonts/CandyshopRegular.otf";
 const TIME_STEP: f32 = 1./60.;
-const PLAYER_SIZE: (f32,f32) = (416., 454.);
-const PLAYER_DEAD_SIZE: (f32,f32) = (601., 512.);
-const ENEMY_SIZE: (f32,f32) = (273., 282.);
 // endregion:    Constants
 
 //Entity, Component, System, Resource
 
 // region:    Resources
 pub struct Materials {
-    player_a: Handle<TextureAtlas>,
-    player_d: Handle<TextureAtlas>,
     enemies: Handle<TextureAtlas>,
-    font: Handle<Font>
-}
-pub struct Sounds {
-    jump: Handle<AudioSource>
+    enemies_sheet: Handle<SpriteSheetMeta>,
+    blue_enemies: Handle<TextureAtlas>,
+    blue_enemies_sheet: Handle<SpriteSheetMeta>,
+    candy: Handle<ColorMaterial>,
+    font: Handle<Font>,
+    background: Handle<Texture>
 }
 pub struct WinSize {
     w: f32,
@@ -44,15 +80,12 @@ pub struct WinSize {
 // endregion:    Resources
 
 // region:    Components
-struct LoopAnim;
-struct Animation{
-    index: u32,
-    size: u32
-}
+pub struct MainCamera;
 // endregion:    Components
 
 fn main() {
-    App::build()
+    let mut app = App::build();
+    app
         .insert_resource(WindowDescriptor {
             title: "Rust Surviver!".to_string(),
             width: 1000.0,
@@ -62,14 +95,37 @@ fn main() {
             ..Default::default()
         })
         .add_plugins(DefaultPlugins)
+        .add_plugin(bevy_kira_audio::AudioPlugin)
         .add_plugin(GameStatePlugin)
         .add_plugin(PlayerPlugin)
         .add_plugin(EnemiesPlugin)
         .add_plugin(ScorePlugin)
         .add_plugin(PhysicsPlugin)
-        .add_startup_system(setup.system())
-        .add_system(animate_looping_sprites.system())
-        .run();
+        .add_plugin(CullingPlugin)
+        .add_plugin(CollectiblesPlugin)
+        .add_plugin(PowerUpsPlugin)
+        .add_plugin(SkinsPlugin)
+        .add_plugin(AnimationPlugin)
+        .add_plugin(EffectsPlugin)
+        .add_plugin(SheetsPlugin)
+        .add_plugin(TweenPlugin)
+        .add_plugin(LayoutPlugin)
+        .add_plugin(HudPlugin)
+        .add_plugin(LeaderboardPlugin)
+        .add_plugin(SettingsPlugin)
+        .add_plugin(ToastsPlugin)
+        .add_plugin(GameAudioPlugin)
+        .add_plugin(RhythmPlugin)
+        .add_plugin(CalibrationPlugin)
+        .add_plugin(ControlsPlugin)
+        .add_plugin(LoadingPlugin)
+        .add_startup_system(setup.system());
+
+    //Pool metrics and the rest of the diagnostics only print in debug builds
+    #[cfg(debug_assertions)]
+    app.add_plugin(LogDiagnosticsPlugin::default());
+
+    app.run();
 }
 
 fn setup(
@@ -77,30 +133,25 @@ fn setup(
     asset_server: Res<AssetServer>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     mut windows: ResMut<Windows>,
-    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
 ){
     let window = windows.get_primary_mut().unwrap();
     
     //Spawn camera
-    commands.spawn_bundle(OrthographicCameraBundle::new_2d());
-    
-    //Load textures
-    let texture_handle_pa = asset_server.load(PLAYER_SPRITE_A);
-    let texture_handle_pd = asset_server.load(PLAYER_SPRITE_D);
-    
-    let texture_atlas_player_d = TextureAtlas::from_grid(texture_handle_pd, Vec2::new(PLAYER_DEAD_SIZE.0, PLAYER_DEAD_SIZE.1), 30, 1);
-    let texture_atlas_player_a = TextureAtlas::from_grid(texture_handle_pa, Vec2::new(PLAYER_SIZE.0, PLAYER_SIZE.1), 33, 2);
+    commands
+        .spawn_bundle(OrthographicCameraBundle::new_2d())
+        .insert(MainCamera);
     
-    let texture_handle_e = asset_server.load(ENEMIES);
-    let texture_atlas_e = TextureAtlas::from_grid(texture_handle_e, Vec2::new(ENEMY_SIZE.0, ENEMY_SIZE.1), 13, 1);
-
     //Create resources
+    let background = asset_server.load(BACKGROUND_IMG);
     commands
         .insert_resource(Materials {
-            player_a: texture_atlases.add(texture_atlas_player_a),
-            player_d: texture_atlases.add(texture_atlas_player_d),
-            enemies: texture_atlases.add(texture_atlas_e),
-            font: asset_server.load(CANDY_FONT)
+            enemies: asset_server.load(atlas_path(ENEMIES).as_str()),
+            enemies_sheet: asset_server.load(ENEMIES),
+            blue_enemies: asset_server.load(atlas_path(BLUE_ENEMIES).as_str()),
+            blue_enemies_sheet: asset_server.load(BLUE_ENEMIES),
+            candy: materials.add(CANDY_COLOR.into()),
+            font: asset_server.load(CANDY_FONT),
+            background: background.clone()
         });
     commands
         .insert_resource(WinSize {
@@ -109,30 +160,10 @@ fn setup(
         });
     commands
         .insert_resource(Gravity(45.*25.));
-    commands
-        .insert_resource(Sounds{
-            jump: asset_server.load("sounds/jump.mp3")
-        });
 
     //Spawn background
     commands.spawn_bundle(SpriteBundle {
-        material: materials.add(asset_server.load(BACKGROUND_IMG).into()),
+        material: materials.add(background.into()),
         ..Default::default()
     });
 }
-
-fn animate_looping_sprites(
-    time: Res<Time>,
-    mut query: Query<(&mut Timer, &mut TextureAtlasSprite, &Animation, With<LoopAnim>)>,
-) {
-    for (mut timer, mut sprite, anim, _) in query.iter_mut() {
-        timer.tick(time.delta());
-        if timer.finished() {
-            if sprite.index >= anim.index && sprite.index+1 < anim.index+anim.size {
-                sprite.index += 1;
-            } else {
-                sprite.index = anim.index;
-            }
-        }
-    }
-}
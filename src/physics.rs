@@ -1,9 +1,13 @@
 use bevy::prelude::*;
+use gamestate::{GameState};
 
 use crate::{TIME_STEP};
+use crate::gamestate;
 
 // region:    Resources
 pub struct Gravity(pub f32);
+//Speed factor applied to the world around the player
+pub struct TimeScale(pub f32);
 // endregion:    Resources
 
 // region:    Components
@@ -16,6 +20,7 @@ pub struct PhysicsPlugin;
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app
+            .insert_resource(TimeScale(1.))
             .add_system(gravity_system.system());
     }
 }
@@ -23,9 +28,12 @@ impl Plugin for PhysicsPlugin {
 
 fn gravity_system(
     gravity: Res<Gravity>,
+    game_state: Res<State<GameState>>,
     mut query: Query<(&mut Velocity, &AffectedByGravity)>
     
 ) {
+    if game_state.current().freezes_world() {return;}
+
     for (mut velocity, affected) in query.iter_mut() {
         if !affected.0 {continue;}
         velocity.0.y -= gravity.0 * TIME_STEP;
@@ -1,23 +1,87 @@
 use bevy::prelude::*;
 use physics::*;
 use enemies::*;
+use pool::*;
+use powerups::*;
+use skins::*;
+use animation::*;
+use sheets::*;
+use score::*;
+use audio::*;
+use controls::*;
+use settings::*;
 use gamestate::{GameState};
-use bevy::sprite::collide_aabb::collide;
+use bevy::sprite::collide_aabb::{collide, Collision};
 
-use crate::{Materials, Sounds, WinSize, TIME_STEP};
-use crate::{Animation, LoopAnim};
-use crate::{PLAYER_SIZE, ENEMY_SIZE};
+use crate::{WinSize, TIME_STEP};
 use crate::physics;
 use crate::enemies;
+use crate::pool;
+use crate::powerups;
+use crate::skins;
+use crate::animation;
+use crate::sheets;
+use crate::score;
+use crate::audio;
+use crate::controls;
+use crate::settings;
 use crate::gamestate;
 
+// region:    Constants
+const STOMP_BOUNCE: f32 = 420.;
+const RUN_SPEED: f32 = 300.;
+const JUMP_SPEED: f32 = 19.*30.;
+//Rising speed kept when the jump button is let go early in one-button mode
+const JUMP_RELEASE_SPEED: f32 = 200.;
+// endregion:    Constants
+
 // region:    Resources
 pub struct PlayerAlive(pub bool);
+
+//Pixels of new ground the player got this frame, after the window edges stopped her
+pub struct PlayerStride {
+    pub gained: f32,
+    //Furthest right the player has been this run, walking back over it earns nothing
+    furthest: f32
+}
+
+//How close a bee must get to the player hitbox to count as a near miss
+pub struct NearMissSettings {
+    pub distance: f32
+}
 // endregion:    Resources
 
 // region:    Components
 pub struct Player;
 struct DeadPlayer;
+//Set while the button that started the current jump is still down
+struct JumpHeld(bool);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlayerAnimState {
+    Idle,
+    Run,
+    Jump,
+    Fall,
+    Land
+}
+
+//Picks the player clip from grounded/airborne/moving transitions
+struct PlayerAnimator {
+    state: Option<PlayerAnimState>
+}
+
+impl PlayerAnimState {
+    fn clip_name(&self) -> &'static str {
+        match self {
+            PlayerAnimState::Idle => "idle",
+            PlayerAnimState::Run => "run",
+            PlayerAnimState::Jump => "jump",
+            PlayerAnimState::Fall => "fall",
+            PlayerAnimState::Land => "land"
+        }
+    }
+}
 // endregion:    Components
 
 // region:    Plugin
@@ -37,7 +101,7 @@ impl Plugin for PlayerPlugin{
                 SystemSet::on_update(GameState::Playing)
                     .with_system(player_check_collision.system())
                     .with_system(player_dead_movement.system())
-                    .with_system(animate_dead_player.system())
+                    .with_system(dead_player_finished.system())
             )
             .add_system_set(
                 SystemSet::on_exit(GameState::Dead)
@@ -46,7 +110,12 @@ impl Plugin for PlayerPlugin{
             )
             .add_system(player_movement.system())
             .add_system(player_jump.system())
-            .insert_resource(PlayerAlive(true));
+            .add_system(player_animation_state.system())
+            .insert_resource(PlayerAlive(true))
+            .insert_resource(PlayerStride { gained: 0., furthest: f32::MIN })
+            .insert_resource(NearMissSettings {
+                distance: 18.
+            });
     }
 }
 // endregion:    Plugin
@@ -55,15 +124,21 @@ impl Plugin for PlayerPlugin{
 
 fn player_spawn(
     mut commands: Commands,
-    materials: Res<Materials>,
+    skins: Res<SkinRegistry>,
+    progress: Res<SkinProgress>,
     win_size: Res<WinSize>
 ){
     let bottom = - win_size.h/2. + 160.0;
     let left = - win_size.w/2. + 135.0;
+    let skin = skins.selected(&progress);
     
     commands
         .spawn_bundle(SpriteSheetBundle  {
-            texture_atlas: materials.player_a.clone(),
+            texture_atlas: skin.alive.atlas.clone(),
+            sprite: TextureAtlasSprite {
+                color: skin.tint,
+                ..Default::default()
+            },
             transform: Transform{
                 translation: Vec3::new(left, bottom, 10.),
                 scale: Vec3::new(0.30, 0.30, 1.),
@@ -73,109 +148,201 @@ fn player_spawn(
         })
         .insert(Player)
         .insert(AffectedByGravity(false))
-        .insert(Timer::from_seconds(0.07, true))
-        .insert(Animation{index: 0,size: 16})
-        .insert(LoopAnim)
+        .insert(JumpHeld(false))
+        .insert(Animation::default())
+        .insert(Sheet(skin.alive.meta.clone()))
+        .insert(PlayerAnimator{
+            state: None
+        })
         .insert(Velocity(Vec2::ZERO));
 }
 
 fn player_movement(
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<Actions>,
+    settings: Res<Settings>,
+    game_state: Res<State<GameState>>,
     win_size: Res<WinSize>,
     alive: Res<PlayerAlive>,
-    mut query: Query<(&mut Velocity, &mut Transform, &mut Animation, 
-        &AffectedByGravity, With<Player>)>
+    sheets: Res<Assets<SpriteSheetMeta>>,
+    mut stride: ResMut<PlayerStride>,
+    mut query: Query<(&mut Velocity, &mut Transform, &Sheet, With<Player>)>
 ){
-    if let Ok((mut velocity, mut transform,mut anim, in_air, _)) = query.single_mut() {
+    stride.gained = 0.;
+
+    if let Ok((mut velocity, mut transform, sheet, _)) = query.single_mut() {
         
-        if !alive.0 {return;}
+        if !alive.0 || game_state.current().freezes_world() {return;}
+
+        //One-button runs face right in place, the speed only drives the run clip and distance
+        let auto_run = settings.one_button && *game_state.current() == GameState::Playing;
 
-        if keyboard_input.pressed(KeyCode::A){
+        if auto_run {
+            if transform.scale.x < 0. {
+                transform.scale.x = -transform.scale.x;
+            }
+            velocity.0.x = RUN_SPEED;
+        } else if actions.pressed(Action::MoveLeft){
             if transform.scale.x > 0. {
                 transform.scale.x = -transform.scale.x;
             }
-            velocity.0.x = -300.;
-        } else if keyboard_input.pressed(KeyCode::D){
+            velocity.0.x = -RUN_SPEED;
+        } else if actions.pressed(Action::MoveRight){
             if transform.scale.x < 0. {
                 transform.scale.x = -transform.scale.x;
             }
-            velocity.0.x = 300.;
+            velocity.0.x = RUN_SPEED;
         } else {
-            if !in_air.0 {
-                anim.index = 0;
-                anim.size = 16;
-            }
             velocity.0.x = 0.;
         };
-
-        if velocity.0.x != 0.0 && !in_air.0 {
-            anim.index = 16;
-            anim.size = 20;
-        }
         
         transform.translation.y += velocity.0.y * TIME_STEP;
-        transform.translation.x += velocity.0.x * TIME_STEP;
+        if !auto_run {
+            transform.translation.x += velocity.0.x * TIME_STEP;
+        }
 
-        let limit = win_size.w/2.0 - PLAYER_SIZE.0 * transform.scale[0].abs()/2.;
+        let width = sheets.get(&sheet.0).map(|meta| meta.cell_size().x).unwrap_or(0.);
+        let limit = win_size.w/2.0 - width * transform.scale[0].abs()/2.;
         transform.translation.x = transform.translation.x.min(limit).max(-limit);
+
+        //Running in place covers the run speed, otherwise only ground past the furthest point counts
+        if auto_run {
+            stride.gained = RUN_SPEED * TIME_STEP;
+        } else if transform.translation.x > stride.furthest {
+            stride.gained = transform.translation.x - stride.furthest;
+            stride.furthest = transform.translation.x;
+        }
     }
 }
 
 fn player_jump(
-    kb: Res<Input<KeyCode>>,
-    audio: Res<Audio>,
-    sounds: Res<Sounds>,
+    actions: Res<Actions>,
+    settings: Res<Settings>,
+    game_state: Res<State<GameState>>,
     win_size: Res<WinSize>,
     alive: Res<PlayerAlive>,
-    mut query: Query<(&mut Transform, &mut Animation, 
-        &mut Velocity, &mut AffectedByGravity, With<Player>)>
+    mut query: Query<(&mut Transform, &mut Velocity, &mut AffectedByGravity, &mut JumpHeld, With<Player>)>
 ){
-    if let Ok((mut transform, mut anim, mut velocity, mut in_air, _)) = query.single_mut() {
-        if !alive.0 {return;}
+    if let Ok((mut transform, mut velocity, mut in_air, mut held, _)) = query.single_mut() {
+        if !alive.0 || game_state.current().freezes_world() {return;}
 
-        if !in_air.0 && (kb.pressed(KeyCode::W)||kb.pressed(KeyCode::Space)) {
+        if !in_air.0 && actions.pressed(Action::Jump) {
             in_air.0 = true;
-            anim.index = 36;
-            anim.size = 30;
-
-            velocity.0.y = 19.*30.;
+            held.0 = true;
 
-            audio.play(sounds.jump.clone());
+            velocity.0.y = JUMP_SPEED;
+        } else if held.0 && !actions.pressed(Action::Jump) {
+            //Holding the button is the only way to pick the jump height, a stomp bounce is left alone
+            held.0 = false;
+            if settings.one_button {
+                velocity.0.y = velocity.0.y.min(JUMP_RELEASE_SPEED);
+            }
         }
         
         if transform.translation.y <  (- win_size.h/2. + 160.0) {
             velocity.0.y = 0.;
             transform.translation.y = - win_size.h/2. + 160.0;
             in_air.0 = false;
+            held.0 = false;
+        }
+    }
+}
+
+fn player_animation_state(
+    sheets: Res<Assets<SpriteSheetMeta>>,
+    mut query: Query<(&mut PlayerAnimator, &mut Animation,
+        &Sheet, &Velocity, &AffectedByGravity, With<Player>)>
+){
+    if let Ok((mut animator, mut anim, sheet, velocity, in_air, _)) = query.single_mut() {
+        let moving = velocity.0.x != 0.;
+        let grounded_state = if moving { PlayerAnimState::Run } else { PlayerAnimState::Idle };
+
+        let next = match (animator.state, in_air.0) {
+            (_, true) if velocity.0.y > 0. => PlayerAnimState::Jump,
+            (_, true) => PlayerAnimState::Fall,
+            (Some(PlayerAnimState::Jump), false) | (Some(PlayerAnimState::Fall), false) => PlayerAnimState::Land,
+            (Some(PlayerAnimState::Land), false) if !anim.finished() && !moving => PlayerAnimState::Land,
+            (_, false) => grounded_state
+        };
+
+        if animator.state == Some(next) {return;}
+
+        //Retried next frame if the sheet is still loading
+        if let Some(clip) = sheets.get(&sheet.0).and_then(|meta| meta.clip(next.clip_name())) {
+            animator.state = Some(next);
+            anim.play(clip);
         }
     }
 }
 
 fn player_check_collision(
     mut commands: Commands,
-    materials: Res<Materials>,
+    skins: Res<SkinRegistry>,
+    progress: Res<SkinProgress>,
     mut alive: ResMut<PlayerAlive>,
-    mut player_query: Query<(Entity, &Transform, With<Player>)>,
-    mut enemy_query: Query<(&Transform ,With<Enemy>)>
+    near_miss: Res<NearMissSettings>,
+    mut powerups: ResMut<ActivePowerUps>,
+    mut pool: ResMut<Pool<Enemy>>,
+    sheets: Res<Assets<SpriteSheetMeta>>,
+    mut score_events: EventWriter<ScoreEvent>,
+    mut sfx: EventWriter<PlaySfx>,
+    mut player_query: Query<(Entity, &Transform, &mut Velocity, &mut JumpHeld, &Sheet, With<Player>)>,
+    mut enemy_query: Query<(Entity, &mut Enemy, &Transform, &Sheet, &mut Pooled, &mut Visible)>
 ){
-    if let Ok((entity, player_tf, _)) = player_query.single_mut() {
+    if let Ok((entity, player_tf, mut velocity, mut held, player_sheet, _)) = player_query.single_mut() {
         if !alive.0 {return;}
 
-        for (enemy_tf, _) in enemy_query.iter_mut() {
-            let margin = 17.;
+        let player_meta = match sheets.get(&player_sheet.0) {
+            Some(meta) => meta,
+            None => return
+        };
+        let (player_pos, player_size) = player_meta.hitbox("body", player_tf);
+        let near_size = player_size + Vec2::splat(near_miss.distance * 2.);
+
+        for (enemy, mut bee, enemy_tf, enemy_sheet, mut pooled, mut visible) in enemy_query.iter_mut() {
+            if !pooled.active {continue;}
 
-            let player_size = Vec2::new(PLAYER_SIZE.0*player_tf.scale[0].abs()-margin, PLAYER_SIZE.1*player_tf.scale[1].abs()-margin);
-            let enemy_size = Vec2::new(ENEMY_SIZE.0*enemy_tf.scale[0].abs()-margin, ENEMY_SIZE.1*enemy_tf.scale[1].abs()-margin);
-            let collision = collide(player_tf.translation, player_size, enemy_tf.translation, enemy_size);
+            let enemy_meta = match sheets.get(&enemy_sheet.0) {
+                Some(meta) => meta,
+                None => continue
+            };
+            let (enemy_pos, enemy_size) = enemy_meta.hitbox("body", enemy_tf);
+            let collision = collide(player_pos, player_size, enemy_pos, enemy_size);
+
+            //Landing on top of a bee squashes it and bounces the player
+            if let (Some(Collision::Top), true) = (collision, velocity.0.y < 0.) {
+                velocity.0.y = STOMP_BOUNCE;
+                held.0 = false;
+                pool.release(enemy, &mut pooled, &mut visible);
+                score_events.send(ScoreEvent{bonus: Bonus::Stomp, position: enemy_pos});
+                sfx.send(PlaySfx(SfxKind::Stomp));
+                continue;
+            }
 
             if let Some(_) = collision{
+                //The shield takes the hit and the bee goes away
+                if powerups.consume_shield() {
+                    pool.release(enemy, &mut pooled, &mut visible);
+                    score_events.send(ScoreEvent{bonus: Bonus::Hit, position: player_pos});
+                    sfx.send(PlaySfx(SfxKind::Hurt));
+                    continue;
+                }
+
                 alive.0 = false;
+                sfx.send(PlaySfx(SfxKind::Death));
                 commands.entity(entity).despawn();
 
                 //Spawn dead player
+                let skin = skins.selected(&progress);
+                let death = sheets.get(&skin.dead.meta)
+                    .and_then(|meta| meta.clip("death"))
+                    .unwrap_or_else(|| AnimationClip::once(0, 1, 0.05));
                 commands
                 .spawn_bundle(SpriteSheetBundle  {
-                    texture_atlas: materials.player_d.clone(),
+                    texture_atlas: skin.dead.atlas.clone(),
+                    sprite: TextureAtlasSprite {
+                        color: skin.tint,
+                        ..Default::default()
+                    },
                     transform: Transform{
                         translation: player_tf.translation,
                         scale: Vec3::new(0.30, 0.30, 1.),
@@ -185,9 +352,22 @@ fn player_check_collision(
                 })
                 .insert(DeadPlayer)
                 .insert(AffectedByGravity(true))
-                .insert(Timer::from_seconds(0.05, true))
-                .insert(Animation{index: 0,size: 30})
+                .insert(Animation::from(death))
+                .insert(Sheet(skin.dead.meta.clone()))
                 .insert(Velocity(Vec2::ZERO));
+                return;
+            }
+
+            //Same boxes grown by the near miss distance, remembered until the bee is past
+            if !bee.near_miss && collide(player_pos, near_size, enemy_pos, enemy_size).is_some() {
+                bee.near_miss = true;
+            }
+
+            //A close call pays more than a dodge and keeps the combo going just the same
+            if !bee.passed && enemy_pos.x < player_pos.x {
+                bee.passed = true;
+                let bonus = if bee.near_miss { Bonus::NearMiss } else { Bonus::Dodge };
+                score_events.send(ScoreEvent{bonus, position: enemy_pos});
             }
         }
     }
@@ -195,6 +375,7 @@ fn player_check_collision(
 
 fn reset_player_pos(
     win_size: Res<WinSize>,
+    mut stride: ResMut<PlayerStride>,
     mut player_query: Query<(&mut Transform, &mut Velocity, With<Player>)>
 ){
     if let Ok((mut transform,mut velocity, _)) = player_query.single_mut() {
@@ -203,6 +384,7 @@ fn reset_player_pos(
         let left = - win_size.w/2. + 135.0;
         transform.translation = Vec3::new(left, bottom, 10.);
         velocity.0 = Vec2::ZERO;
+        stride.furthest = left;
     }
 }
 
@@ -224,28 +406,19 @@ fn player_dead_movement(
     }
 }
 
-fn animate_dead_player(
+fn dead_player_finished(
     mut game_state: ResMut<State<GameState>>,
-    time: Res<Time>,
-    mut query: Query<(&mut Timer, &mut TextureAtlasSprite, &Animation, With<DeadPlayer>)>,
+    mut finished: EventReader<AnimationFinished>,
+    query: Query<&DeadPlayer>,
 ) {
-    for (mut timer, mut sprite, anim, _) in query.iter_mut() {
-        if sprite.index+1 >= anim.index+anim.size {
-            match game_state.set(GameState::Dead) {
-                Ok(_) => {println!("Dead State");}
-                Err(_) => {}
-            }
-            return;
-        }
-        
-        timer.tick(time.delta());
-        if timer.finished() {
-            if sprite.index >= anim.index && sprite.index+1 < anim.index+anim.size {
-                sprite.index += 1;
-            } else {
-                sprite.index = anim.index;
-            }
+    for event in finished.iter() {
+        if query.get(event.entity).is_err() {continue;}
+
+        match game_state.set(GameState::Dead) {
+            Ok(_) => {println!("Dead State");}
+            Err(_) => {}
         }
+        return;
     }
 }
 
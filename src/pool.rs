@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+use bevy::diagnostic::{Diagnostic, DiagnosticId, Diagnostics};
+use std::marker::PhantomData;
+
+// region:    Resources
+//Free list of hidden entities of kind T ready to be recycled
+pub struct Pool<T> {
+    free: Vec<Entity>,
+    hits: u32,
+    misses: u32,
+    _kind: PhantomData<T>
+}
+
+impl<T> Default for Pool<T> {
+    fn default() -> Self {
+        Pool {
+            free: Vec::new(),
+            hits: 0,
+            misses: 0,
+            _kind: PhantomData
+        }
+    }
+}
+
+impl<T> Pool<T> {
+    //Returns a parked entity to reuse, or None when a new one must be spawned
+    pub fn acquire(&mut self) -> Option<Entity> {
+        match self.free.pop() {
+            Some(entity) => {
+                self.hits += 1;
+                Some(entity)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    //Hides the entity and parks it until the next acquire
+    pub fn release(&mut self, entity: Entity, pooled: &mut Pooled, visible: &mut Visible) {
+        if !pooled.active {return;}
+        pooled.active = false;
+        visible.is_visible = false;
+        self.free.push(entity);
+    }
+
+    pub fn hits(&self) -> u32 {
+        self.hits
+    }
+
+    pub fn misses(&self) -> u32 {
+        self.misses
+    }
+
+    pub fn free(&self) -> usize {
+        self.free.len()
+    }
+}
+
+struct PoolDiagnostics<T> {
+    name: &'static str,
+    ids: [DiagnosticId; 3],
+    _kind: PhantomData<T>
+}
+// endregion:    Resources
+
+// region:    Components
+pub struct Pooled {
+    pub active: bool
+}
+// endregion:    Components
+
+// region:    Plugin
+pub struct PoolPlugin<T> {
+    name: &'static str,
+    ids: [DiagnosticId; 3],
+    _kind: PhantomData<T>
+}
+
+impl<T> PoolPlugin<T> {
+    //Diagnostic ids are in the order hits, misses, free
+    pub fn new(name: &'static str, ids: [DiagnosticId; 3]) -> Self {
+        PoolPlugin {
+            name,
+            ids,
+            _kind: PhantomData
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> Plugin for PoolPlugin<T> {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .insert_resource(Pool::<T>::default())
+            .insert_resource(PoolDiagnostics::<T> {
+                name: self.name,
+                ids: self.ids,
+                _kind: PhantomData
+            })
+            .add_startup_system(setup_pool_diagnostics::<T>.system())
+            .add_system(pool_diagnostics::<T>.system());
+    }
+}
+// endregion:    Plugin
+
+fn setup_pool_diagnostics<T: Send + Sync + 'static>(
+    pool_diagnostics: Res<PoolDiagnostics<T>>,
+    mut diagnostics: ResMut<Diagnostics>
+){
+    let name = pool_diagnostics.name;
+    let [hits, misses, free] = pool_diagnostics.ids;
+
+    diagnostics.add(Diagnostic::new(hits, format!("{}_hits", name), 20));
+    diagnostics.add(Diagnostic::new(misses, format!("{}_misses", name), 20));
+    diagnostics.add(Diagnostic::new(free, format!("{}_free", name), 20));
+}
+
+fn pool_diagnostics<T: Send + Sync + 'static>(
+    pool: Res<Pool<T>>,
+    pool_diagnostics: Res<PoolDiagnostics<T>>,
+    mut diagnostics: ResMut<Diagnostics>
+){
+    let [hits, misses, free] = pool_diagnostics.ids;
+
+    diagnostics.add_measurement(hits, pool.hits() as f64);
+    diagnostics.add_measurement(misses, pool.misses() as f64);
+    diagnostics.add_measurement(free, pool.free() as f64);
+}
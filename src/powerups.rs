@@ -0,0 +1,337 @@
+use bevy::prelude::*;
+use bevy::sprite::collide_aabb::collide;
+use physics::*;
+use player::*;
+use enemies::*;
+use collectibles::*;
+use culling::*;
+use skins::*;
+use sheets::*;
+use score::*;
+use audio::*;
+use gamestate::{GameState, GameMode};
+
+use rand::{thread_rng, Rng};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::{TIME_STEP};
+use crate::physics;
+use crate::player;
+use crate::enemies;
+use crate::collectibles;
+use crate::culling;
+use crate::skins;
+use crate::sheets;
+use crate::score;
+use crate::audio;
+use crate::gamestate;
+
+// region:    Constants
+const POWERUP_SIZE: f32 = 34.;
+const POWERUP_DURATION: f32 = 6.;
+//Timers of the same kind stack up to this many seconds
+const MAX_STACKED_DURATION: f32 = 15.;
+const MAX_MULTIPLIER: u32 = 4;
+const MAGNET_RADIUS: f32 = 260.;
+const MAGNET_SPEED: f32 = 520.;
+const SLOW_MOTION_SCALE: f32 = 0.5;
+const SHIELD_TINT: Color = Color::rgb(0.6, 0.85, 1.0);
+// endregion:    Constants
+
+// region:    Resources
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum PowerUpKind {
+    Shield,
+    Magnet,
+    SlowMotion,
+    ScoreMultiplier
+}
+
+impl PowerUpKind {
+    const ALL: [PowerUpKind; 4] = [
+        PowerUpKind::Shield,
+        PowerUpKind::Magnet,
+        PowerUpKind::SlowMotion,
+        PowerUpKind::ScoreMultiplier
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PowerUpKind::Shield => "Shield",
+            PowerUpKind::Magnet => "Magnet",
+            PowerUpKind::SlowMotion => "Slow",
+            PowerUpKind::ScoreMultiplier => "Score"
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            PowerUpKind::Shield => Color::rgb(0.35, 0.65, 0.95),
+            PowerUpKind::Magnet => Color::rgb(0.85, 0.2, 0.25),
+            PowerUpKind::SlowMotion => Color::rgb(0.55, 0.4, 0.9),
+            PowerUpKind::ScoreMultiplier => Color::rgb(0.98, 0.8, 0.2)
+        }
+    }
+}
+
+//Timed effects currently granted to the player
+#[derive(Default)]
+pub struct ActivePowerUps {
+    timers: HashMap<PowerUpKind, Timer>,
+    multiplier: u32
+}
+
+impl ActivePowerUps {
+    //Shield refreshes, multiplier grows a level, the rest extend their timer
+    pub fn grant(&mut self, kind: PowerUpKind) {
+        let remaining = self.remaining(kind);
+        let duration = match kind {
+            PowerUpKind::Shield => POWERUP_DURATION,
+            PowerUpKind::ScoreMultiplier => {
+                self.multiplier = (self.multiplier.max(1) + 1).min(MAX_MULTIPLIER);
+                POWERUP_DURATION
+            }
+            _ => (remaining + POWERUP_DURATION).min(MAX_STACKED_DURATION)
+        };
+        self.timers.insert(kind, Timer::from_seconds(duration, false));
+    }
+
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.timers.contains_key(&kind)
+    }
+
+    pub fn remaining(&self, kind: PowerUpKind) -> f32 {
+        self.timers.get(&kind)
+            .map(|timer| (timer.duration() - timer.elapsed()).as_secs_f32())
+            .unwrap_or(0.)
+    }
+
+    //Uses up the shield, returns false when there was none to absorb the hit
+    pub fn consume_shield(&mut self) -> bool {
+        self.timers.remove(&PowerUpKind::Shield).is_some()
+    }
+
+    pub fn score_multiplier(&self) -> f32 {
+        if self.is_active(PowerUpKind::ScoreMultiplier) {
+            self.multiplier as f32
+        } else {
+            1.
+        }
+    }
+
+    //Active effects sorted for display
+    pub fn iter(&self) -> impl Iterator<Item = (PowerUpKind, f32)> + '_ {
+        PowerUpKind::ALL.iter()
+            .filter(move |kind| self.is_active(**kind))
+            .map(move |kind| (*kind, self.remaining(*kind)))
+    }
+
+    fn tick(&mut self, delta: Duration) {
+        for timer in self.timers.values_mut() {
+            timer.tick(delta);
+        }
+        self.timers.retain(|_, timer| !timer.finished());
+        if !self.is_active(PowerUpKind::ScoreMultiplier) {
+            self.multiplier = 1;
+        }
+    }
+}
+
+struct PowerUpSpawnTimer(Timer);
+
+struct PowerUpMaterials(HashMap<PowerUpKind, Handle<ColorMaterial>>);
+// endregion:    Resources
+
+// region:    Components
+pub struct PowerUp(pub PowerUpKind);
+// endregion:    Components
+
+// region:    Plugin
+pub struct PowerUpsPlugin;
+impl Plugin for PowerUpsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .add_startup_system(powerup_materials.system())
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(reset_powerups.system())
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(powerup_spawn.system())
+                    .with_system(powerup_movement.system())
+                    .with_system(powerup_pickup.system())
+                    .with_system(powerup_timers.system())
+                    .with_system(magnet_pull.system())
+                    .with_system(shield_tint.system())
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Dead)
+                    .with_system(powerup_cleanup.system())
+            )
+            .insert_resource(PowerUpSpawnTimer(Timer::from_seconds(9., true)))
+            .insert_resource(ActivePowerUps::default());
+    }
+}
+// endregion:    Plugin
+
+fn powerup_materials(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>
+){
+    let handles = PowerUpKind::ALL.iter()
+        .map(|kind| (*kind, materials.add(kind.color().into())))
+        .collect();
+    commands.insert_resource(PowerUpMaterials(handles));
+}
+
+fn powerup_spawn(
+    mut commands: Commands,
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mode: Res<GameMode>,
+    mut spawn_timer: ResMut<PowerUpSpawnTimer>,
+    director: Res<SpawnDirector>,
+    enemy_settings: Res<EnemySpawnSettings>,
+    materials: Res<PowerUpMaterials>,
+    view: Res<ViewRect>,
+    player_alive: Res<PlayerAlive>
+){
+    if !player_alive.0 {return;}
+
+    spawn_timer.0.tick(time.delta().mul_f32(time_scale.0));
+    if !spawn_timer.0.finished() {
+        return;
+    }
+
+    //Slowing the bees down would pull them off the beat
+    let kinds: Vec<PowerUpKind> = PowerUpKind::ALL.iter()
+        .copied()
+        .filter(|kind| *mode == GameMode::Endless || *kind != PowerUpKind::SlowMotion)
+        .collect();
+
+    let mut rng = thread_rng();
+    let kind = kinds[rng.gen_range(0..kinds.len())];
+    let margin = POWERUP_SIZE / 2.;
+    let lane = director.safe_lane();
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            material: materials.0[&kind].clone(),
+            sprite: Sprite::new(Vec2::new(POWERUP_SIZE, POWERUP_SIZE)),
+            transform: Transform {
+                translation: Vec3::new(view.max.x + margin, SpawnDirector::lane_y(lane), 12.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(PowerUp(kind))
+        .insert(CullBounds{margin})
+        .insert(Velocity(Vec2::new(enemy_settings.speed, 0.)));
+}
+
+fn powerup_movement(
+    time_scale: Res<TimeScale>,
+    mut query: Query<(&Velocity, &mut Transform, With<PowerUp>)>
+){
+    for (velocity, mut transform, _) in query.iter_mut() {
+        transform.translation.x += velocity.0.x * time_scale.0 * TIME_STEP;
+    }
+}
+
+fn powerup_pickup(
+    mut commands: Commands,
+    mut active: ResMut<ActivePowerUps>,
+    mut score_events: EventWriter<ScoreEvent>,
+    mut sfx: EventWriter<PlaySfx>,
+    player_alive: Res<PlayerAlive>,
+    sheets: Res<Assets<SpriteSheetMeta>>,
+    player_query: Query<(&Transform, &Sheet, With<Player>)>,
+    powerup_query: Query<(Entity, &Transform, &PowerUp)>
+){
+    if !player_alive.0 {return;}
+
+    if let Ok((player_tf, sheet, _)) = player_query.single() {
+        let (player_pos, player_size) = match sheets.get(&sheet.0) {
+            Some(meta) => meta.hitbox("pickup", player_tf),
+            None => return
+        };
+
+        for (entity, powerup_tf, powerup) in powerup_query.iter() {
+            let collision = collide(player_pos, player_size, powerup_tf.translation, Vec2::new(POWERUP_SIZE, POWERUP_SIZE));
+
+            if collision.is_some() {
+                active.grant(powerup.0);
+                score_events.send(ScoreEvent{bonus: Bonus::PowerUp, position: powerup_tf.translation});
+                sfx.send(PlaySfx(SfxKind::Pickup));
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+fn powerup_timers(
+    time: Res<Time>,
+    mut active: ResMut<ActivePowerUps>,
+    mut time_scale: ResMut<TimeScale>
+){
+    active.tick(time.delta());
+
+    let scale = if active.is_active(PowerUpKind::SlowMotion) { SLOW_MOTION_SCALE } else { 1. };
+    if time_scale.0 != scale {
+        time_scale.0 = scale;
+    }
+}
+
+fn magnet_pull(
+    active: Res<ActivePowerUps>,
+    player_query: Query<(&Transform, With<Player>)>,
+    mut candy_query: Query<(&mut Transform, With<Candy>, Without<Player>)>
+){
+    if !active.is_active(PowerUpKind::Magnet) {return;}
+
+    if let Ok((player_tf, _)) = player_query.single() {
+        let target = player_tf.translation.truncate();
+
+        for (mut candy_tf, _, _) in candy_query.iter_mut() {
+            let offset = target - candy_tf.translation.truncate();
+            let distance = offset.length();
+            if distance > MAGNET_RADIUS || distance < 1. {continue;}
+
+            let step = (MAGNET_SPEED * TIME_STEP).min(distance);
+            let pull = offset / distance * step;
+            candy_tf.translation.x += pull.x;
+            candy_tf.translation.y += pull.y;
+        }
+    }
+}
+
+fn shield_tint(
+    active: Res<ActivePowerUps>,
+    skins: Res<SkinRegistry>,
+    progress: Res<SkinProgress>,
+    mut query: Query<(&mut TextureAtlasSprite, With<Player>)>
+){
+    if let Ok((mut sprite, _)) = query.single_mut() {
+        let skin_tint = skins.selected(&progress).tint;
+        sprite.color = if active.is_active(PowerUpKind::Shield) { SHIELD_TINT } else { skin_tint };
+    }
+}
+
+fn reset_powerups(
+    mut active: ResMut<ActivePowerUps>,
+    mut time_scale: ResMut<TimeScale>
+){
+    *active = ActivePowerUps::default();
+    time_scale.0 = 1.;
+}
+
+fn powerup_cleanup(
+    mut commands: Commands,
+    query: Query<(Entity, With<PowerUp>)>
+){
+    for (entity, _) in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
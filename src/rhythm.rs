@@ -0,0 +1,227 @@
+use bevy::prelude::*;
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+use std::cmp::Ordering;
+use player::*;
+use enemies::*;
+use culling::*;
+use sheets::*;
+use settings::*;
+use audio::*;
+use toasts::*;
+use gamestate::{GameState, GameMode};
+
+use crate::{Materials};
+use crate::player;
+use crate::enemies;
+use crate::culling;
+use crate::sheets;
+use crate::settings;
+use crate::audio;
+use crate::toasts;
+use crate::gamestate;
+
+// region:    Constants
+const BEATMAP: &str = "beatmaps/meadow.beatmap";
+// endregion:    Constants
+
+// region:    Assets
+//Spawns timed against a music track, notes are kept sorted by time
+#[derive(Debug, Deserialize, TypeUuid)]
+#[uuid = "9d3a6c1e-2b7f-4e58-a0c4-61f2e8b7d953"]
+pub struct Beatmap {
+    pub music: String,
+    //Seconds before the track and the notes start over
+    pub length: f32,
+    pub notes: Vec<Note>
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Note {
+    //Seconds into the track when the bee reaches the player
+    pub time: f32,
+    pub lane: usize,
+    pub enemy: EnemyKind
+}
+// endregion:    Assets
+
+impl Beatmap {
+    //Notes have to fit in a lane and inside one pass of the track
+    fn validate(&self) -> Result<(), anyhow::Error> {
+        if !(self.length > 0.) || !self.length.is_finite() {
+            anyhow::bail!("length {} must be a positive number of seconds", self.length);
+        }
+        for (i, note) in self.notes.iter().enumerate() {
+            if !(note.time >= 0. && note.time < self.length) {
+                anyhow::bail!("note {} at {}s is outside the track length of {}s", i, note.time, self.length);
+            }
+            if note.lane >= SpawnDirector::lane_count() {
+                anyhow::bail!("note {} is in lane {} but there are only {} lanes", i, note.lane, SpawnDirector::lane_count());
+            }
+        }
+        Ok(())
+    }
+}
+
+// region:    Resources
+pub struct RhythmAssets {
+    pub beatmap: Handle<Beatmap>
+}
+
+//Progress through the beatmap during a rhythm run
+#[derive(Default)]
+struct RhythmRun {
+    //Seconds since the track started, not wrapped on loops
+    clock: f32,
+    next: usize,
+    //Times every note has been sent
+    loops: u32,
+    //Times the track has played through
+    encores: u32
+}
+// endregion:    Resources
+
+// region:    Loader
+#[derive(Default)]
+struct BeatmapLoader;
+
+impl AssetLoader for BeatmapLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let mut beatmap: Beatmap = ron::de::from_bytes(bytes)?;
+            beatmap.validate()?;
+            beatmap.notes.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(Ordering::Equal));
+
+            load_context.set_default_asset(LoadedAsset::new(beatmap));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["beatmap"]
+    }
+}
+// endregion:    Loader
+
+// region:    Plugin
+pub struct RhythmPlugin;
+impl Plugin for RhythmPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .add_asset::<Beatmap>()
+            .init_asset_loader::<BeatmapLoader>()
+            .add_startup_system(load_beatmap.system())
+            .add_system(register_beatmap_track.system())
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(reset_rhythm.system())
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(beatmap_spawn.system())
+            )
+            .insert_resource(RhythmRun::default());
+    }
+}
+// endregion:    Plugin
+
+fn load_beatmap(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>
+){
+    commands.insert_resource(RhythmAssets {
+        beatmap: asset_server.load(BEATMAP)
+    });
+}
+
+//The track is named by the beatmap, so it is only known once the beatmap is read
+fn register_beatmap_track(
+    asset_server: Res<AssetServer>,
+    beatmaps: Res<Assets<Beatmap>>,
+    mut sounds: ResMut<Sounds>,
+    mut events: EventReader<AssetEvent<Beatmap>>
+){
+    for event in events.iter() {
+        match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                if let Some(beatmap) = beatmaps.get(handle) {
+                    sounds.music.insert(MusicKind::Rhythm, asset_server.load(beatmap.music.as_str()));
+                }
+            }
+            AssetEvent::Removed { .. } => {}
+        }
+    }
+}
+
+fn reset_rhythm(
+    mut run: ResMut<RhythmRun>
+){
+    *run = RhythmRun::default();
+}
+
+//Sends each note early enough for its bee to reach the player on the beat
+fn beatmap_spawn(
+    time: Res<Time>,
+    mode: Res<GameMode>,
+    settings: Res<Settings>,
+    enemy_settings: Res<EnemySpawnSettings>,
+    materials: Res<Materials>,
+    sheets: Res<Assets<SpriteSheetMeta>>,
+    view: Res<ViewRect>,
+    rhythm: Res<RhythmAssets>,
+    beatmaps: Res<Assets<Beatmap>>,
+    mut run: ResMut<RhythmRun>,
+    mut spawns: EventWriter<SpawnEnemy>,
+    mut toasts: EventWriter<Toast>,
+    player_query: Query<&Transform, With<Player>>
+){
+    if *mode != GameMode::Rhythm {return;}
+
+    let beatmap = match beatmaps.get(&rhythm.beatmap) {
+        Some(beatmap) if !beatmap.notes.is_empty() && beatmap.length > 0. => beatmap,
+        _ => return
+    };
+    let player_x = match player_query.single() {
+        Ok(transform) => transform.translation.x,
+        Err(_) => return
+    };
+
+    //Follows the track, which the slow motion power-up does not slow down
+    run.clock += time.delta_seconds();
+
+    let encores = (run.clock / beatmap.length) as u32;
+    if encores > run.encores {
+        run.encores = encores;
+        toasts.send(Toast(format!("Encore x{}", encores)));
+    }
+
+    loop {
+        let note = beatmap.notes[run.next];
+        let meta = match sheets.get(&note.enemy.sheet(&materials)) {
+            Some(meta) => meta,
+            None => return
+        };
+
+        //The beat is heard late by the latency, the bee has to be just as late
+        let hit_time = note.time + run.loops as f32 * beatmap.length + settings.rhythm_latency;
+        let travel = (spawn_x(&view, meta) - player_x) / enemy_settings.speed_of(note.enemy);
+        if run.clock < hit_time - travel {break;}
+
+        //Notes too early in the track for their bee to make it are left out of the first pass
+        if hit_time - travel >= 0. {
+            spawns.send(SpawnEnemy { lane: note.lane, kind: note.enemy });
+        }
+
+        run.next += 1;
+        if run.next == beatmap.notes.len() {
+            run.next = 0;
+            run.loops += 1;
+        }
+    }
+}
@@ -1,13 +1,74 @@
 use bevy::prelude::*;
 use player::*;
-use gamestate::{GameState};
+use powerups::*;
+use tween::*;
+use layout::*;
+use gamestate::{GameState, MenuItem, MenuCursor, MenuButton};
 
 use crate::{Materials};
 use crate::player;
+use crate::powerups;
+use crate::tween;
+use crate::layout;
 use crate::gamestate;
 
+// region:    Constants
+const PIXELS_PER_METER: f32 = 50.;
+const POINTS_PER_METER: f32 = 1.;
+const DODGE_POINTS: f32 = 10.;
+const STOMP_POINTS: f32 = 50.;
+const CANDY_POINTS: f32 = 5.;
+const POWERUP_POINTS: f32 = 20.;
+const NEAR_MISS_POINTS: f32 = 25.;
+const POPUP_LIFETIME: f32 = 0.8;
+const POPUP_RISE: f32 = 60.;
+//Height of callouts above the points popup they come with
+const CALLOUT_OFFSET: f32 = 80.;
+//Each dodge in a row adds this much to the combo multiplier
+const COMBO_STEP: f32 = 0.25;
+const MAX_COMBO: f32 = 4.;
+// endregion:    Constants
+
+// region:    Resources
+#[derive(Default)]
+pub struct Score {
+    pub points: f32,
+    //Meters run by the player
+    pub distance: f32,
+    //Dodges in a row since the last hit
+    pub streak: u32
+}
+
+impl Score {
+    pub fn combo(&self) -> f32 {
+        (1. + self.streak as f32 * COMBO_STEP).min(MAX_COMBO)
+    }
+}
+// endregion:    Resources
+
+// region:    Events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bonus {
+    //A bee went past the player without touching her
+    Dodge,
+    //A bee went past after getting within the near miss distance, style points on top of the combo
+    NearMiss,
+    Stomp,
+    Candy,
+    PowerUp,
+    //A bee hit the player but the shield absorbed it, the near-hit that ends the combo
+    Hit
+}
+
+pub struct ScoreEvent {
+    pub bonus: Bonus,
+    //Where the popup shows up
+    pub position: Vec3
+}
+// endregion:    Events
+
 // region:    Components
-struct Score(f32);
+struct MenuRoot;
 // endregion:    Components
 
 // region:    Plugin
@@ -15,119 +76,121 @@ pub struct ScorePlugin;
 impl Plugin for ScorePlugin {
     fn build(&self, app: &mut AppBuilder) {
         app
+            .add_event::<ScoreEvent>()
+            .insert_resource(Score::default())
             .add_system_set(
                 SystemSet::on_enter(GameState::MainMenu)
                     .with_system(menu_text_spawn.system())
             )
+            .add_system_set(
+                SystemSet::on_update(GameState::MainMenu)
+                    .with_system(update_menu_text.system())
+            )
             .add_system_set(
                 SystemSet::on_exit(GameState::MainMenu)
-                    .with_system(menu_text_cleanup.system())
+                    .with_system(despawn_screen::<MenuRoot>.system())
             )
             .add_system_set(
-                SystemSet::on_enter(GameState::Playing)
-                    .with_system(score_spawn.system())
+                SystemSet::on_pause(GameState::MainMenu)
+                    .with_system(despawn_screen::<MenuRoot>.system())
             )
             .add_system_set(
-                SystemSet::on_update(GameState::Playing)
-                    .with_system(update_score.system())
+                SystemSet::on_resume(GameState::MainMenu)
+                    .with_system(menu_text_spawn.system())
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(reset_score.system())
             )
             .add_system_set(
-                SystemSet::on_exit(GameState::Dead)
-                    .with_system(score_cleanup.system())
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(distance_score.system())
+                    .with_system(bonus_score.system())
             );
     }
 }
 // endregion:    Plugin
 
-fn score_spawn(
-    mut commands: Commands,
-    materials: Res<Materials>
-) {
-    //Score text
-    commands.
-        spawn_bundle(Text2dBundle {
-            text: Text::with_section(
-                "Score: ",
-                TextStyle {
-                    font: materials.font.clone(),
-                    font_size: 50.0,
-                    color: Color::rgb(0.0823, 0.0627, 0.1686),
-                },
-                TextAlignment {
-                    vertical: VerticalAlign::Center,
-                    horizontal: HorizontalAlign::Center,
-                }
-            ),
-            transform: Transform {
-                translation: Vec3::new(-380.,190.,30.),
-                ..Default::default()
-            },
-            ..Default::default()
-        });
-    //Score value
-    commands
-        .spawn_bundle(Text2dBundle {
-            text: Text::with_section(
-                "",
-                TextStyle {
-                    font: materials.font.clone(),
-                    font_size: 50.0,
-                    color: Color::rgb(0.0823, 0.0627, 0.1686),
-                },
-                TextAlignment {
-                    vertical: VerticalAlign::Center,
-                    horizontal: HorizontalAlign::Center,
-                }
-            ),
-            transform: Transform {
-                translation: Vec3::new(-255.,188.,30.),
-                ..Default::default()
-            },
-            ..Default::default()
-        })
-        .insert(Score(0.));
+fn reset_score(
+    mut score: ResMut<Score>
+){
+    *score = Score::default();
 }
 
-fn update_score(
-    time: Res<Time>,
-    mut query: Query<(&mut Text, &mut Score)>,
+//Only ground actually covered counts, standing still earns nothing
+fn distance_score(
+    mut score: ResMut<Score>,
+    stride: Res<PlayerStride>,
+    powerups: Res<ActivePowerUps>,
     player_alive: Res<PlayerAlive>
 ){
-    
     if !player_alive.0 {return;}
-    
-    if let Ok((mut text, mut score)) = query.single_mut() {
-        score.0 += time.delta_seconds();
-        let value = score.0 as u32;
-        let string = format!("{:05}", value);
 
-        text.sections[0].value = string;
-    }
+    let meters = stride.gained / PIXELS_PER_METER;
+    if meters == 0. {return;}
 
+    score.distance += meters;
+    score.points += meters * POINTS_PER_METER * score.combo() * powerups.score_multiplier();
 }
 
-fn score_cleanup(
+fn bonus_score(
     mut commands: Commands,
-    mut query: Query<(Entity, &Text)>
+    mut score: ResMut<Score>,
+    powerups: Res<ActivePowerUps>,
+    materials: Res<Materials>,
+    mut events: EventReader<ScoreEvent>
 ){
-    for (entity, _) in query.iter_mut() {
-        commands.entity(entity).despawn();
+    for event in events.iter() {
+        let combo = score.combo();
+        let points = match event.bonus {
+            Bonus::Dodge => {
+                score.streak += 1;
+                DODGE_POINTS
+            }
+            Bonus::NearMiss => {
+                score.streak += 1;
+                spawn_popup(&mut commands, &materials, "NEAR MISS!", event.position + Vec3::new(0., CALLOUT_OFFSET, 0.));
+                NEAR_MISS_POINTS
+            }
+            Bonus::Stomp => STOMP_POINTS,
+            Bonus::Candy => CANDY_POINTS,
+            Bonus::PowerUp => POWERUP_POINTS,
+            Bonus::Hit => {
+                score.streak = 0;
+                continue;
+            }
+        };
+
+        let gained = points * combo * powerups.score_multiplier();
+        score.points += gained;
+        spawn_popup(&mut commands, &materials, &format!("+{}", gained as u32), event.position);
+
+        //Call out every whole step of the combo
+        let new_combo = score.combo();
+        if new_combo > combo && new_combo.fract() == 0. {
+            spawn_popup(&mut commands, &materials, &format!("x{}", new_combo), event.position + Vec3::new(0., CALLOUT_OFFSET, 0.));
+        }
     }
 }
 
-fn menu_text_spawn(
-    mut commands: Commands,
-    materials: Res<Materials>
+//World space text that rises and fades out
+fn spawn_popup(
+    commands: &mut Commands,
+    materials: &Materials,
+    value: &str,
+    position: Vec3
 ){
-    //Press enter text
-    commands.
-        spawn_bundle(Text2dBundle {
+    let from = Vec3::new(position.x, position.y, 40.);
+    let to = from + Vec3::new(0., POPUP_RISE, 0.);
+
+    commands
+        .spawn_bundle(Text2dBundle {
             text: Text::with_section(
-                "<Press Enter>",
+                value,
                 TextStyle {
                     font: materials.font.clone(),
-                    font_size: 80.0,
-                    color: Color::rgb(0.0823, 0.0627, 0.1686),
+                    font_size: 36.0,
+                    color: HIGHLIGHT_COLOR,
                 },
                 TextAlignment {
                     vertical: VerticalAlign::Center,
@@ -135,18 +198,51 @@ fn menu_text_spawn(
                 }
             ),
             transform: Transform {
-                translation: Vec3::new(0.,50.,30.),
+                translation: from,
                 ..Default::default()
             },
             ..Default::default()
-        });
+        })
+        .insert(Tween::new(TranslationLens { from, to }, POPUP_LIFETIME, Ease::QuadOut))
+        .insert(Tween::new(TextAlphaLens { from: 1., to: 0. }, POPUP_LIFETIME, Ease::Linear).despawn_when_done());
 }
 
-fn menu_text_cleanup(
+fn menu_text_spawn(
     mut commands: Commands,
-    mut text_query: Query<(Entity, With<Text>)>
+    materials: Res<Materials>,
+    ui: Res<UiMaterials>
+){
+    let style = TextStyle {
+        font: materials.font.clone(),
+        font_size: 80.0,
+        color: TEXT_COLOR,
+    };
+
+    commands
+        .spawn_bundle(centered(&ui))
+        .insert(MenuRoot)
+        .with_children(|menu| {
+            for item in MenuItem::ALL.iter() {
+                //Play stands out, the rest is smaller
+                let font_size = if *item == MenuItem::Play { 80.0 } else { 40.0 };
+                menu.spawn_bundle(label(vec![TextSection {
+                    value: item.label().to_string(),
+                    style: TextStyle { font_size, ..style.clone() }
+                }]))
+                    .insert(MenuButton(*item))
+                    .insert(Interaction::default());
+            }
+        });
+}
+
+fn update_menu_text(
+    cursor: Res<MenuCursor>,
+    mut query: Query<(&MenuButton, &mut Text)>
 ){
-    if let Ok((entity, _)) = text_query.single_mut() {
-        commands.entity(entity).despawn();
+    for (button, mut text) in query.iter_mut() {
+        let color = if button.0 == MenuItem::ALL[cursor.0] { HIGHLIGHT_COLOR } else { TEXT_COLOR };
+        if text.sections[0].style.color != color {
+            text.sections[0].style.color = color;
+        }
     }
-}
\ No newline at end of file
+}
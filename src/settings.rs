@@ -0,0 +1,333 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use hud::*;
+use layout::*;
+use audio::*;
+use controls::*;
+use gamestate::{GameState};
+
+use crate::{Materials};
+use crate::hud;
+use crate::layout;
+use crate::audio;
+use crate::controls;
+use crate::gamestate;
+use crate::storage;
+
+// region:    Constants
+const SETTINGS_FILE: &str = "settings.ron";
+const VOLUME_STEP: f32 = 0.1;
+const SLIDER_STEPS: usize = 10;
+//Rows shown at once, the rest are on the next pages
+const ROWS_PER_PAGE: usize = 8;
+// endregion:    Constants
+
+// region:    Resources
+//Player preferences, fields missing from older files take their default
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub hidden_hud: Vec<HudItem>,
+    pub audio: AudioSettings,
+    pub bindings: Bindings,
+    //Auto-run with a single jump button, clicks and touches included
+    pub one_button: bool,
+    //Seconds between a beat being played and being heard, measured on the calibration screen
+    pub rhythm_latency: f32
+}
+
+impl Settings {
+    pub fn shows(&self, item: HudItem) -> bool {
+        !self.hidden_hud.contains(&item)
+    }
+
+    pub fn toggle(&mut self, item: HudItem) {
+        if self.shows(item) {
+            self.hidden_hud.push(item);
+        } else {
+            self.hidden_hud.retain(|hidden| *hidden != item);
+        }
+    }
+
+    pub fn save(&self) {
+        storage::save(SETTINGS_FILE, self);
+    }
+}
+
+struct SettingsCursor(usize);
+// endregion:    Resources
+
+#[derive(Clone, Copy)]
+enum SettingsRow {
+    Mute,
+    Volume(VolumeChannel),
+    OneButton,
+    Binding(Action),
+    Hud(HudItem)
+}
+
+//Audio first, the control scheme, then one row per action and per HUD item
+fn rows() -> Vec<SettingsRow> {
+    let mut rows = vec![SettingsRow::Mute];
+    rows.extend(VolumeChannel::ALL.iter().map(|channel| SettingsRow::Volume(*channel)));
+    rows.push(SettingsRow::OneButton);
+    rows.extend(Action::ALL.iter().map(|action| SettingsRow::Binding(*action)));
+    rows.extend(HudItem::ALL.iter().map(|item| SettingsRow::Hud(*item)));
+    rows
+}
+
+fn page_count() -> usize {
+    (rows().len() + ROWS_PER_PAGE - 1) / ROWS_PER_PAGE
+}
+
+// region:    Components
+struct SettingsRoot;
+//Slot on the page the text shows, the row depends on the page
+struct SettingsRowText(usize);
+//Shows the page and turns to the next one when clicked
+struct SettingsPageText;
+// endregion:    Components
+
+// region:    Plugin
+pub struct SettingsPlugin;
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .add_system_set(
+                SystemSet::on_enter(GameState::Settings)
+                    .with_system(settings_spawn.system())
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Settings)
+                    .with_system(settings_input.system())
+                    .with_system(update_settings_text.system())
+                    .with_system(update_settings_page.system())
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Settings)
+                    .with_system(despawn_screen::<SettingsRoot>.system())
+            )
+            .insert_resource(SettingsCursor(0))
+            .insert_resource(storage::load::<Settings>(SETTINGS_FILE));
+    }
+}
+// endregion:    Plugin
+
+fn settings_spawn(
+    mut commands: Commands,
+    materials: Res<Materials>,
+    ui: Res<UiMaterials>,
+    mut cursor: ResMut<SettingsCursor>
+){
+    cursor.0 = 0;
+
+    let style = TextStyle {
+        font: materials.font.clone(),
+        font_size: 32.0,
+        color: TEXT_COLOR,
+    };
+
+    //Rows are separate nodes so each one can be clicked
+    commands
+        .spawn_bundle(centered(&ui))
+        .insert(SettingsRoot)
+        .with_children(|screen| {
+            screen.spawn_bundle(label(vec![TextSection {
+                value: "Settings".to_string(),
+                style: TextStyle { font_size: 44.0, ..style.clone() }
+            }]));
+            for slot in 0..ROWS_PER_PAGE {
+                screen.spawn_bundle(label(vec![TextSection {
+                    value: String::new(),
+                    style: style.clone()
+                }]))
+                    .insert(SettingsRowText(slot))
+                    .insert(Interaction::default());
+            }
+            screen.spawn_bundle(label(vec![TextSection {
+                value: String::new(),
+                style: TextStyle { font_size: 26.0, ..style.clone() }
+            }]))
+                .insert(SettingsPageText)
+                .insert(Interaction::default());
+            screen.spawn_bundle(label(vec![TextSection {
+                value: "< Back".to_string(),
+                style: TextStyle { font_size: 26.0, ..style.clone() }
+            }]))
+                .insert(ClickAction(Action::Back))
+                .insert(Interaction::default());
+        });
+}
+
+fn settings_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    button_input: Res<Input<GamepadButton>>,
+    actions: Res<Actions>,
+    mut cursor: ResMut<SettingsCursor>,
+    mut settings: ResMut<Settings>,
+    mut rebinding: ResMut<Rebinding>,
+    mut sfx: EventWriter<PlaySfx>,
+    row_query: Query<(&Interaction, &SettingsRowText), Changed<Interaction>>,
+    page_query: Query<&Interaction, (Changed<Interaction>, With<SettingsPageText>)>
+){
+    //Waiting for a key, escape or any pad button gives up on the change, a reserved key keeps it waiting
+    if let Some(action) = rebinding.0 {
+        if button_input.get_just_pressed().next().is_some() {
+            rebinding.0 = None;
+            sfx.send(PlaySfx(SfxKind::MenuMove));
+        } else if let Some(key) = keyboard_input.get_just_pressed().next() {
+            if *key == KeyCode::Escape {
+                rebinding.0 = None;
+                sfx.send(PlaySfx(SfxKind::MenuSelect));
+            } else if settings.bindings.bind(action, *key) {
+                settings.save();
+                rebinding.0 = None;
+                sfx.send(PlaySfx(SfxKind::MenuSelect));
+            } else {
+                sfx.send(PlaySfx(SfxKind::MenuMove));
+            }
+        }
+        return;
+    }
+
+    let rows = rows();
+    let count = rows.len();
+    let page = cursor.0 / ROWS_PER_PAGE;
+
+    //Moving past the last row of a page lands on the next one
+    if actions.just_pressed(Action::MenuUp) {
+        cursor.0 = (cursor.0 + count - 1) % count;
+        sfx.send(PlaySfx(SfxKind::MenuMove));
+    }
+    if actions.just_pressed(Action::MenuDown) {
+        cursor.0 = (cursor.0 + 1) % count;
+        sfx.send(PlaySfx(SfxKind::MenuMove));
+    }
+
+    //Left and right move sliders, enter flips toggles
+    let mut step = if actions.just_pressed(Action::MenuLeft) {
+        -VOLUME_STEP
+    } else if actions.just_pressed(Action::MenuRight) {
+        VOLUME_STEP
+    } else {
+        0.
+    };
+    let mut confirm = actions.just_pressed(Action::Confirm);
+
+    if page_query.iter().any(|interaction| *interaction == Interaction::Clicked) {
+        cursor.0 = (page + 1) % page_count() * ROWS_PER_PAGE;
+        sfx.send(PlaySfx(SfxKind::MenuMove));
+        return;
+    }
+
+    //Clicking a row selects it and acts like confirm, sliders go up and wrap around
+    for (interaction, row_text) in row_query.iter() {
+        let i = page * ROWS_PER_PAGE + row_text.0;
+        if *interaction != Interaction::Clicked || i >= count {continue;}
+        cursor.0 = i;
+        match rows[cursor.0] {
+            SettingsRow::Volume(channel) => {
+                step = if settings.audio.volume(channel) >= 1. { -1. } else { VOLUME_STEP };
+            }
+            _ => confirm = true
+        }
+    }
+
+    let changed = match rows[cursor.0] {
+        SettingsRow::Mute if confirm => {
+            settings.audio.muted = !settings.audio.muted;
+            true
+        }
+        SettingsRow::Volume(channel) if step != 0. => {
+            settings.audio.adjust(channel, step);
+            true
+        }
+        SettingsRow::OneButton if confirm => {
+            settings.one_button = !settings.one_button;
+            true
+        }
+        SettingsRow::Binding(action) if confirm => {
+            rebinding.0 = Some(action);
+            sfx.send(PlaySfx(SfxKind::MenuMove));
+            false
+        }
+        SettingsRow::Hud(item) if confirm => {
+            settings.toggle(item);
+            true
+        }
+        _ => false
+    };
+
+    if changed {
+        settings.save();
+        sfx.send(PlaySfx(SfxKind::MenuSelect));
+    }
+}
+
+fn update_settings_text(
+    cursor: Res<SettingsCursor>,
+    settings: Res<Settings>,
+    rebinding: Res<Rebinding>,
+    mut query: Query<(&SettingsRowText, &mut Text)>
+){
+    let rows = rows();
+    let page = cursor.0 / ROWS_PER_PAGE;
+    for (row_text, mut text) in query.iter_mut() {
+        let i = page * ROWS_PER_PAGE + row_text.0;
+        //The last page can have empty slots
+        if i >= rows.len() {
+            if !text.sections[0].value.is_empty() {
+                text.sections[0].value = String::new();
+            }
+            continue;
+        }
+
+        let value = match rows[i] {
+            SettingsRow::Mute => {
+                let status = if settings.audio.muted { "on" } else { "off" };
+                format!("Mute <M>  ({})", status)
+            }
+            SettingsRow::Volume(channel) => {
+                let filled = (settings.audio.volume(channel) * SLIDER_STEPS as f32).round() as usize;
+                format!("{}  [{}{}]", channel.label(), "#".repeat(filled), "-".repeat(SLIDER_STEPS - filled))
+            }
+            SettingsRow::OneButton => {
+                let status = if settings.one_button { "on" } else { "off" };
+                format!("One-button mode  ({})", status)
+            }
+            SettingsRow::Binding(action) if rebinding.0 == Some(action) => {
+                format!("{}  [press a key]", action.label())
+            }
+            SettingsRow::Binding(action) => {
+                let keys: Vec<String> = settings.bindings.keys(action).iter()
+                    .map(|key| format!("{:?}", key))
+                    .collect();
+                format!("{}  [{}]", action.label(), keys.join(", "))
+            }
+            SettingsRow::Hud(item) => {
+                let status = if settings.shows(item) { "on" } else { "off" };
+                format!("{}  ({})", item.label(), status)
+            }
+        };
+
+        let color = if i == cursor.0 { HIGHLIGHT_COLOR } else { TEXT_COLOR };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+        if text.sections[0].style.color != color {
+            text.sections[0].style.color = color;
+        }
+    }
+}
+
+fn update_settings_page(
+    cursor: Res<SettingsCursor>,
+    mut query: Query<&mut Text, With<SettingsPageText>>
+){
+    let value = format!("Page {}/{}  >", cursor.0 / ROWS_PER_PAGE + 1, page_count());
+    if let Ok(mut text) = query.single_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use bevy::prelude::*;
+use bevy::asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset};
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+use animation::*;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::animation;
+
+// region:    Assets
+//Layout, clips and hitboxes shipped next to a sprite sheet image
+#[derive(Debug, Deserialize, TypeUuid)]
+#[uuid = "5f0b2f7e-4a8c-4d3b-9c1e-7a2d6e9b3c41"]
+pub struct SpriteSheetMeta {
+    pub image: String,
+    pub cell_size: (f32, f32),
+    pub columns: usize,
+    pub rows: usize,
+    #[serde(default)]
+    pub padding: (f32, f32),
+    //Normalized point of the cell the entity stands on, (0,0) is the top left corner
+    #[serde(default = "default_pivot")]
+    pub pivot: (f32, f32),
+    #[serde(default)]
+    clips: HashMap<String, ClipMeta>,
+    #[serde(default)]
+    hitboxes: HashMap<String, Hitbox>
+}
+
+#[derive(Debug, Deserialize)]
+struct ClipMeta {
+    index: u32,
+    size: u32,
+    mode: PlaybackMode,
+    frame_duration: f32,
+    #[serde(default)]
+    markers: Vec<FrameMarker>
+}
+
+//Box in sheet pixels, offset from the cell center
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Hitbox {
+    pub size: (f32, f32),
+    #[serde(default)]
+    pub offset: (f32, f32)
+}
+// endregion:    Assets
+
+fn default_pivot() -> (f32, f32) {
+    (0.5, 0.5)
+}
+
+impl SpriteSheetMeta {
+    pub fn cell_size(&self) -> Vec2 {
+        Vec2::new(self.cell_size.0, self.cell_size.1)
+    }
+
+    pub fn clip(&self, name: &str) -> Option<AnimationClip> {
+        self.clips.get(name).map(|clip| {
+            AnimationClip::new(clip.index, clip.size, clip.mode, clip.frame_duration)
+                .with_markers(clip.markers.clone())
+        })
+    }
+
+    //Named hitbox placed in the world, the whole cell when the sheet does not define it
+    pub fn hitbox(&self, name: &str, transform: &Transform) -> (Vec3, Vec2) {
+        let hitbox = self.hitboxes.get(name).copied().unwrap_or(Hitbox {
+            size: self.cell_size,
+            offset: (0., 0.)
+        });
+        let scale = transform.scale.truncate().abs();
+        let offset = Vec2::new(hitbox.offset.0, -hitbox.offset.1) * scale;
+        let size = Vec2::new(hitbox.size.0, hitbox.size.1) * scale;
+
+        (transform.translation + offset.extend(0.), size)
+    }
+
+    //World position of the pivot, e.g. the feet of a character
+    pub fn pivot_position(&self, transform: &Transform) -> Vec3 {
+        let from_center = Vec2::new(self.pivot.0 - 0.5, 0.5 - self.pivot.1) * self.cell_size();
+        transform.translation + (from_center * transform.scale.truncate().abs()).extend(0.)
+    }
+
+    //Clips that would stall the frame timer, read past the atlas or mark a frame they never play
+    fn validate(&self) -> Result<(), anyhow::Error> {
+        let frames = (self.columns * self.rows) as u32;
+        for (name, clip) in self.clips.iter() {
+            if !(clip.frame_duration > 0.) {
+                anyhow::bail!("clip \"{}\" has a frame duration of {}, it must be above 0", name, clip.frame_duration);
+            }
+            if clip.size == 0 {
+                anyhow::bail!("clip \"{}\" has no frames", name);
+            }
+            if clip.index + clip.size > frames {
+                anyhow::bail!("clip \"{}\" plays frames {}..{} but the sheet only has {}", name, clip.index, clip.index + clip.size, frames);
+            }
+            if let Some(marker) = clip.markers.iter().find(|marker| marker.frame >= clip.size) {
+                anyhow::bail!("clip \"{}\" has a {:?} marker on frame {} but only {} frames", name, marker.kind, marker.frame, clip.size);
+            }
+        }
+        Ok(())
+    }
+}
+
+// region:    Components
+//Metadata of the sheet an entity is drawn from
+pub struct Sheet(pub Handle<SpriteSheetMeta>);
+// endregion:    Components
+
+// region:    Loader
+#[derive(Default)]
+struct SpriteSheetLoader;
+
+impl AssetLoader for SpriteSheetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let meta: SpriteSheetMeta = ron::de::from_bytes(bytes)?;
+            meta.validate()?;
+
+            //The atlas is a labeled sub asset so handles exist before loading ends
+            let image_path = AssetPath::new(PathBuf::from(&meta.image), None);
+            let texture = load_context.get_handle(image_path.clone());
+            let padding = Vec2::new(meta.padding.0, meta.padding.1);
+            let atlas = TextureAtlas::from_grid_with_padding(texture, meta.cell_size(), meta.columns, meta.rows, padding);
+
+            load_context.set_labeled_asset("atlas", LoadedAsset::new(atlas).with_dependency(image_path));
+            load_context.set_default_asset(LoadedAsset::new(meta));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sheet"]
+    }
+}
+// endregion:    Loader
+
+// region:    Plugin
+pub struct SheetsPlugin;
+impl Plugin for SheetsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .add_asset::<SpriteSheetMeta>()
+            .init_asset_loader::<SpriteSheetLoader>();
+    }
+}
+// endregion:    Plugin
+
+//Handle to the atlas built from a sheet metadata file
+pub fn atlas_path(sheet_path: &str) -> String {
+    format!("{}#atlas", sheet_path)
+}
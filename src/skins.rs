@@ -0,0 +1,318 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use collectibles::*;
+use player::*;
+use sheets::*;
+use layout::*;
+use toasts::*;
+use audio::*;
+use controls::*;
+use gamestate::{GameState};
+
+use crate::{Materials};
+use crate::collectibles;
+use crate::player;
+use crate::sheets;
+use crate::layout;
+use crate::toasts;
+use crate::audio;
+use crate::controls;
+use crate::gamestate;
+use crate::storage;
+
+// region:    Constants
+const PLAYER_SHEET_A: &str = "sprites/cute_girl_alive.sheet";
+const PLAYER_SHEET_D: &str = "sprites/cute_girl_dead.sheet";
+const SKINS_FILE: &str = "skins.ron";
+
+//The variants share the cute girl sheets and set themselves apart by tint
+const SKINS: [SkinDef; 4] = [
+    SkinDef {
+        name: "Cute Girl",
+        price: 0,
+        alive: PLAYER_SHEET_A,
+        dead: PLAYER_SHEET_D,
+        tint: Color::WHITE
+    },
+    SkinDef {
+        name: "Bubblegum",
+        price: 25,
+        alive: PLAYER_SHEET_A,
+        dead: PLAYER_SHEET_D,
+        tint: Color::rgb(1.0, 0.7, 0.85)
+    },
+    SkinDef {
+        name: "Mint",
+        price: 40,
+        alive: PLAYER_SHEET_A,
+        dead: PLAYER_SHEET_D,
+        tint: Color::rgb(0.7, 1.0, 0.8)
+    },
+    SkinDef {
+        name: "Midnight",
+        price: 75,
+        alive: PLAYER_SHEET_A,
+        dead: PLAYER_SHEET_D,
+        tint: Color::rgb(0.55, 0.55, 0.9)
+    }
+];
+// endregion:    Constants
+
+//Paths point to sheet metadata files, which carry the frame layout and clips
+struct SkinDef {
+    name: &'static str,
+    price: u32,
+    alive: &'static str,
+    dead: &'static str,
+    tint: Color
+}
+
+// region:    Resources
+#[derive(Clone)]
+pub struct SkinSheet {
+    pub atlas: Handle<TextureAtlas>,
+    pub meta: Handle<SpriteSheetMeta>
+}
+
+pub struct Skin {
+    pub name: &'static str,
+    pub price: u32,
+    pub alive: SkinSheet,
+    pub dead: SkinSheet,
+    pub tint: Color
+}
+
+pub struct SkinRegistry {
+    skins: Vec<Skin>
+}
+
+impl SkinRegistry {
+    pub fn get(&self, name: &str) -> &Skin {
+        self.skins.iter()
+            .find(|skin| skin.name == name)
+            .unwrap_or(&self.skins[0])
+    }
+
+    pub fn selected(&self, progress: &SkinProgress) -> &Skin {
+        self.get(&progress.selected)
+    }
+
+    pub fn sheets(&self) -> impl Iterator<Item = &SkinSheet> {
+        self.skins.iter().flat_map(|skin| vec![&skin.alive, &skin.dead])
+    }
+}
+
+//Unlocked and selected skins, persisted between sessions
+#[derive(Serialize, Deserialize)]
+pub struct SkinProgress {
+    pub unlocked: Vec<String>,
+    pub selected: String
+}
+
+impl Default for SkinProgress {
+    fn default() -> Self {
+        SkinProgress {
+            unlocked: vec![SKINS[0].name.to_string()],
+            selected: SKINS[0].name.to_string()
+        }
+    }
+}
+
+impl SkinProgress {
+    pub fn is_unlocked(&self, name: &str) -> bool {
+        self.unlocked.iter().any(|unlocked| unlocked == name)
+    }
+}
+
+//Skin highlighted in the shop
+struct ShopCursor(usize);
+// endregion:    Resources
+
+// region:    Components
+struct ShopRoot;
+struct ShopTitle;
+//Index of the skin the text shows
+struct ShopRow(usize);
+// endregion:    Components
+
+// region:    Plugin
+pub struct SkinsPlugin;
+impl Plugin for SkinsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .add_startup_system(load_skins.system())
+            .add_system_set(
+                SystemSet::on_enter(GameState::Shop)
+                    .with_system(shop_spawn.system())
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Shop)
+                    .with_system(shop_input.system())
+                    .with_system(update_shop_text.system())
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Shop)
+                    .with_system(despawn_screen::<ShopRoot>.system())
+            )
+            .add_system(apply_skin.system())
+            .insert_resource(ShopCursor(0))
+            .insert_resource(storage::load::<SkinProgress>(SKINS_FILE));
+    }
+}
+// endregion:    Plugin
+
+fn load_skins(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>
+){
+    let sheet = |path: &str| SkinSheet {
+        atlas: asset_server.load(atlas_path(path).as_str()),
+        meta: asset_server.load(path)
+    };
+
+    let skins = SKINS.iter()
+        .map(|def| Skin {
+            name: def.name,
+            price: def.price,
+            alive: sheet(def.alive),
+            dead: sheet(def.dead),
+            tint: def.tint
+        })
+        .collect();
+
+    commands.insert_resource(SkinRegistry { skins });
+}
+
+//Keeps the live player in sync with the selected skin
+fn apply_skin(
+    registry: Res<SkinRegistry>,
+    progress: Res<SkinProgress>,
+    mut query: Query<(&mut Handle<TextureAtlas>, &mut TextureAtlasSprite, &mut Sheet, With<Player>)>
+){
+    if !progress.is_changed() {return;}
+
+    let skin = registry.selected(&progress);
+    for (mut atlas, mut sprite, mut sheet, _) in query.iter_mut() {
+        *atlas = skin.alive.atlas.clone();
+        sheet.0 = skin.alive.meta.clone();
+        sprite.color = skin.tint;
+    }
+}
+
+fn shop_spawn(
+    mut commands: Commands,
+    materials: Res<Materials>,
+    ui: Res<UiMaterials>,
+    progress: Res<SkinProgress>,
+    mut cursor: ResMut<ShopCursor>
+){
+    cursor.0 = SKINS.iter()
+        .position(|def| def.name == progress.selected)
+        .unwrap_or(0);
+
+    let style = TextStyle {
+        font: materials.font.clone(),
+        font_size: 46.0,
+        color: TEXT_COLOR,
+    };
+
+    //Title with the wallet, then one clickable node per skin
+    commands
+        .spawn_bundle(centered(&ui))
+        .insert(ShopRoot)
+        .with_children(|shop| {
+            shop.spawn_bundle(label(vec![TextSection {
+                value: String::new(),
+                style: TextStyle { font_size: 60.0, ..style.clone() }
+            }])).insert(ShopTitle);
+            for i in 0..SKINS.len() {
+                shop.spawn_bundle(label(vec![TextSection {
+                    value: String::new(),
+                    style: style.clone()
+                }]))
+                    .insert(ShopRow(i))
+                    .insert(Interaction::default());
+            }
+            shop.spawn_bundle(label(vec![TextSection {
+                value: "< Back".to_string(),
+                style: TextStyle { font_size: 30.0, ..style.clone() }
+            }]))
+                .insert(ClickAction(Action::Back))
+                .insert(Interaction::default());
+        });
+}
+
+fn shop_input(
+    actions: Res<Actions>,
+    mut cursor: ResMut<ShopCursor>,
+    mut wallet: ResMut<Wallet>,
+    mut progress: ResMut<SkinProgress>,
+    mut toasts: EventWriter<Toast>,
+    mut sfx: EventWriter<PlaySfx>,
+    row_query: Query<(&Interaction, &ShopRow), Changed<Interaction>>
+){
+    if actions.just_pressed(Action::MenuUp) {
+        cursor.0 = (cursor.0 + SKINS.len() - 1) % SKINS.len();
+        sfx.send(PlaySfx(SfxKind::MenuMove));
+    }
+    if actions.just_pressed(Action::MenuDown) {
+        cursor.0 = (cursor.0 + 1) % SKINS.len();
+        sfx.send(PlaySfx(SfxKind::MenuMove));
+    }
+
+    //Clicking a skin picks it as if it was highlighted and confirmed
+    let clicked = row_query.iter().find(|(interaction, _)| **interaction == Interaction::Clicked);
+    if let Some((_, row)) = clicked {
+        cursor.0 = row.0;
+    }
+
+    if !actions.just_pressed(Action::Confirm) && clicked.is_none() {return;}
+
+    //Buy the skin if needed, then wear it
+    let skin = &SKINS[cursor.0];
+    if !progress.is_unlocked(skin.name) {
+        if wallet.candies < skin.price {return;}
+        wallet.candies -= skin.price;
+        progress.unlocked.push(skin.name.to_string());
+        wallet.save();
+        toasts.send(Toast(format!("Unlocked {}!", skin.name)));
+    }
+    progress.selected = skin.name.to_string();
+    storage::save(SKINS_FILE, &*progress);
+    sfx.send(PlaySfx(SfxKind::MenuSelect));
+}
+
+fn update_shop_text(
+    cursor: Res<ShopCursor>,
+    wallet: Res<Wallet>,
+    progress: Res<SkinProgress>,
+    mut title_query: Query<&mut Text, With<ShopTitle>>,
+    mut row_query: Query<(&ShopRow, &mut Text), Without<ShopTitle>>
+){
+    let title = format!("Shop - {} candies", wallet.candies);
+    if let Ok(mut text) = title_query.single_mut() {
+        if text.sections[0].value != title {
+            text.sections[0].value = title;
+        }
+    }
+
+    for (row, mut text) in row_query.iter_mut() {
+        let skin = &SKINS[row.0];
+        let status = if progress.selected == skin.name {
+            "worn".to_string()
+        } else if progress.is_unlocked(skin.name) {
+            "owned".to_string()
+        } else {
+            format!("{} candies", skin.price)
+        };
+
+        let value = format!("{}  ({})", skin.name, status);
+        let color = if row.0 == cursor.0 { HIGHLIGHT_COLOR } else { TEXT_COLOR };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+        if text.sections[0].style.color != color {
+            text.sections[0].style.color = color;
+        }
+    }
+}
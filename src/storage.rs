@@ -0,0 +1,34 @@
+use serde::{de::DeserializeOwned, Serialize};
+use ron::ser::{to_string_pretty, PrettyConfig};
+use std::fs;
+use std::path::PathBuf;
+
+// region:    Constants
+const SAVE_DIR: &str = "save";
+// endregion:    Constants
+
+fn save_path(file: &str) -> PathBuf {
+    PathBuf::from(SAVE_DIR).join(file)
+}
+
+//Reads a save file, falling back to the default value when missing or invalid
+pub fn load<T: DeserializeOwned + Default>(file: &str) -> T {
+    fs::read_to_string(save_path(file))
+        .ok()
+        .and_then(|content| ron::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save<T: Serialize>(file: &str, value: &T) {
+    let result = to_string_pretty(value, PrettyConfig::default())
+        .map_err(|e| e.to_string())
+        .and_then(|content| {
+            fs::create_dir_all(SAVE_DIR)
+                .and_then(|_| fs::write(save_path(file), content))
+                .map_err(|e| e.to_string())
+        });
+
+    if let Err(e) = result {
+        eprintln!("Could not save {}: {}", file, e);
+    }
+}
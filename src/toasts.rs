@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+use enemies::*;
+use score::*;
+use leaderboard::*;
+use tween::*;
+use layout::*;
+use gamestate::{GameState};
+
+use std::collections::VecDeque;
+
+use crate::{Materials};
+use crate::enemies;
+use crate::score;
+use crate::leaderboard;
+use crate::tween;
+use crate::layout;
+use crate::gamestate;
+
+// region:    Constants
+const TOAST_MARGIN: f32 = 20.;
+//Starts this far past the right edge and slides in
+const TOAST_HIDDEN: f32 = -420.;
+const TOAST_SLIDE: f32 = 0.35;
+const TOAST_HOLD: f32 = 1.8;
+const SCORE_MILESTONES: [u32; 7] = [100, 250, 500, 1000, 2500, 5000, 10000];
+// endregion:    Constants
+
+// region:    Events
+//Short message shown in a corner without blocking play
+pub struct Toast(pub String);
+// endregion:    Events
+
+// region:    Resources
+//Messages waiting their turn, only one toast is on screen at a time
+struct ToastQueue {
+    pending: VecDeque<String>,
+    shown: Option<Entity>,
+    //Time left for the current step, slide in and hold or slide out
+    timer: Timer
+}
+
+//What has already been announced during the current run
+#[derive(Default)]
+struct RunMilestones {
+    score: usize,
+    new_best: bool,
+    tier: u32
+}
+// endregion:    Resources
+
+// region:    Plugin
+pub struct ToastsPlugin;
+impl Plugin for ToastsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .add_event::<Toast>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(reset_milestones.system())
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(score_toasts.system())
+                    .with_system(difficulty_toasts.system())
+            )
+            .add_system(queue_toasts.system())
+            .add_system(show_toasts.system())
+            .insert_resource(ToastQueue {
+                pending: VecDeque::new(),
+                shown: None,
+                timer: Timer::from_seconds(0., false)
+            })
+            .insert_resource(RunMilestones::default());
+    }
+}
+// endregion:    Plugin
+
+fn queue_toasts(
+    mut queue: ResMut<ToastQueue>,
+    mut events: EventReader<Toast>
+){
+    for Toast(message) in events.iter() {
+        queue.pending.push_back(message.clone());
+    }
+}
+
+fn show_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    materials: Res<Materials>,
+    mut queue: ResMut<ToastQueue>
+){
+    queue.timer.tick(time.delta());
+    if !queue.timer.finished() {return;}
+
+    //Current toast is done holding, send it away
+    if let Some(entity) = queue.shown.take() {
+        commands.entity(entity)
+            .insert(Tween::new(UiRightLens { from: TOAST_MARGIN, to: TOAST_HIDDEN }, TOAST_SLIDE, Ease::QuadOut))
+            .insert(Tween::new(TextAlphaLens { from: 1., to: 0. }, TOAST_SLIDE, Ease::Linear).despawn_when_done());
+        queue.timer = Timer::from_seconds(TOAST_SLIDE, false);
+        return;
+    }
+
+    let message = match queue.pending.pop_front() {
+        Some(message) => message,
+        None => return
+    };
+
+    let entity = commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    right: Val::Px(TOAST_HIDDEN),
+                    bottom: Val::Px(TOAST_MARGIN),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                message,
+                TextStyle {
+                    font: materials.font.clone(),
+                    font_size: 40.0,
+                    color: HIGHLIGHT_COLOR,
+                },
+                Default::default()
+            ),
+            ..Default::default()
+        })
+        .insert(Tween::new(UiRightLens { from: TOAST_HIDDEN, to: TOAST_MARGIN }, TOAST_SLIDE, Ease::BackOut))
+        .id();
+
+    queue.shown = Some(entity);
+    queue.timer = Timer::from_seconds(TOAST_SLIDE + TOAST_HOLD, false);
+}
+
+fn reset_milestones(
+    mut milestones: ResMut<RunMilestones>,
+    spawn_timer: Res<SpawnTimer>
+){
+    *milestones = RunMilestones {
+        tier: spawn_timer.tier(),
+        ..Default::default()
+    };
+}
+
+fn score_toasts(
+    score: Res<Score>,
+    scores: Res<HighScores>,
+    mut milestones: ResMut<RunMilestones>,
+    mut toasts: EventWriter<Toast>
+){
+    if !score.is_changed() {return;}
+    let points = score.points as u32;
+
+    while milestones.score < SCORE_MILESTONES.len() && points >= SCORE_MILESTONES[milestones.score] {
+        toasts.send(Toast(format!("{} points!", SCORE_MILESTONES[milestones.score])));
+        milestones.score += 1;
+    }
+
+    //Only a table with a record in it can be beaten
+    let best = scores.best();
+    if !milestones.new_best && best > 0 && points > best {
+        milestones.new_best = true;
+        toasts.send(Toast("New best!".to_string()));
+    }
+}
+
+fn difficulty_toasts(
+    spawn_timer: Res<SpawnTimer>,
+    mut milestones: ResMut<RunMilestones>,
+    mut toasts: EventWriter<Toast>
+){
+    let tier = spawn_timer.tier();
+    if tier > milestones.tier {
+        milestones.tier = tier;
+        toasts.send(Toast(format!("Difficulty tier {}", tier)));
+    }
+}
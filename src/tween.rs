@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+use bevy::ecs::component::Component;
+
+// region:    Easing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ease {
+    Linear,
+    QuadOut,
+    //Overshoots the end value and settles back, reads as a bounce
+    BackOut
+}
+
+impl Ease {
+    pub fn apply(&self, t: f32) -> f32 {
+        match self {
+            Ease::Linear => t,
+            Ease::QuadOut => 1. - (1. - t) * (1. - t),
+            Ease::BackOut => {
+                let c1 = 1.70158;
+                let c3 = c1 + 1.;
+                1. + c3 * (t - 1.).powi(3) + c1 * (t - 1.).powi(2)
+            }
+        }
+    }
+}
+// endregion:    Easing
+
+// region:    Lenses
+//Property of a component a tween drives from 0 to 1
+pub trait Lens: Send + Sync + 'static {
+    type Target: Component;
+    fn lerp(&self, target: &mut Self::Target, ratio: f32);
+}
+
+//Works for sprites and world space text alike
+pub struct TranslationLens {
+    pub from: Vec3,
+    pub to: Vec3
+}
+
+impl Lens for TranslationLens {
+    type Target = Transform;
+    fn lerp(&self, target: &mut Transform, ratio: f32) {
+        target.translation = self.from + (self.to - self.from) * ratio;
+    }
+}
+
+//Text ignores its transform scale, so punches resize the font instead
+pub struct FontSizeLens {
+    pub from: f32,
+    pub to: f32
+}
+
+impl Lens for FontSizeLens {
+    type Target = Text;
+    fn lerp(&self, target: &mut Text, ratio: f32) {
+        let size = self.from + (self.to - self.from) * ratio;
+        for section in target.sections.iter_mut() {
+            section.style.font_size = size;
+        }
+    }
+}
+
+//Distance of an absolutely positioned ui node from the right window edge
+pub struct UiRightLens {
+    pub from: f32,
+    pub to: f32
+}
+
+impl Lens for UiRightLens {
+    type Target = Style;
+    fn lerp(&self, target: &mut Style, ratio: f32) {
+        target.position.right = Val::Px(self.from + (self.to - self.from) * ratio);
+    }
+}
+
+//Alpha of every section of a text
+pub struct TextAlphaLens {
+    pub from: f32,
+    pub to: f32
+}
+
+impl Lens for TextAlphaLens {
+    type Target = Text;
+    fn lerp(&self, target: &mut Text, ratio: f32) {
+        let alpha = self.from + (self.to - self.from) * ratio;
+        for section in target.sections.iter_mut() {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
+// endregion:    Lenses
+
+// region:    Components
+pub struct Tween<L: Lens> {
+    lens: L,
+    ease: Ease,
+    timer: Timer,
+    despawn: bool
+}
+
+impl<L: Lens> Tween<L> {
+    pub fn new(lens: L, duration: f32, ease: Ease) -> Self {
+        Tween {
+            lens,
+            ease,
+            timer: Timer::from_seconds(duration, false),
+            despawn: false
+        }
+    }
+
+    //Despawns the entity instead of just removing the tween
+    pub fn despawn_when_done(self) -> Self {
+        Tween { despawn: true, ..self }
+    }
+}
+// endregion:    Components
+
+// region:    Plugin
+pub struct TweenPlugin;
+impl Plugin for TweenPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .add_system(tween_system::<TranslationLens>.system())
+            .add_system(tween_system::<FontSizeLens>.system())
+            .add_system(tween_system::<TextAlphaLens>.system())
+            .add_system(tween_system::<UiRightLens>.system());
+    }
+}
+// endregion:    Plugin
+
+fn tween_system<L: Lens>(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Tween<L>, &mut L::Target)>
+){
+    for (entity, mut tween, mut target) in query.iter_mut() {
+        tween.timer.tick(time.delta());
+
+        let ratio = tween.ease.apply(tween.timer.percent());
+        tween.lens.lerp(&mut target, ratio);
+
+        if tween.timer.just_finished() {
+            if tween.despawn {
+                commands.entity(entity).despawn_recursive();
+            } else {
+                commands.entity(entity).remove::<Tween<L>>();
+            }
+        }
+    }
+}